@@ -0,0 +1,89 @@
+// compares the grid broadphase against brute force loops, at the sort of entity counts swarm mode
+// reaches. run with `cargo bench`.
+#[path = "../src/grid.rs"]
+mod grid;
+
+use grid::Grid;
+use macroquad::prelude::*;
+use macroquad::rand::gen_range;
+use std::time::Instant;
+
+const WORLD_WIDTH: f32 = 1280.0;
+const WORLD_HEIGHT: f32 = 720.0;
+const CELL: f32 = 64.0;
+// roughly a lemon's separation range, and the player/knife kill distance
+const NEIGHBOUR_RANGE: f32 = 40.0;
+const KILL_RANGE: f32 = 30.0;
+const ITERS: u32 = 20;
+
+fn main() {
+    macroquad::rand::srand(1);
+
+    for count in [64, 256, 1024, 4096] {
+        let positions: Vec<Vec2> = (0..count)
+            .map(|_| vec2(gen_range(0.0, WORLD_WIDTH), gen_range(0.0, WORLD_HEIGHT)))
+            .collect();
+        let player = vec2(WORLD_WIDTH / 2.0, WORLD_HEIGHT / 2.0);
+        let mut grid = Grid::new(WORLD_WIDTH, WORLD_HEIGHT, CELL);
+
+        // one point against everything, like the knife and player checks
+        let brute_point = time(|| {
+            let range_sq = KILL_RANGE * KILL_RANGE;
+            positions
+                .iter()
+                .filter(|p| p.distance_squared(player) < range_sq)
+                .count()
+        });
+        let grid_point = time(|| {
+            grid.build(positions.iter().copied());
+            query_count(&grid, &positions, player, KILL_RANGE)
+        });
+
+        // everything against everything, like lemon separation
+        let brute_all = time(|| {
+            let range_sq = NEIGHBOUR_RANGE * NEIGHBOUR_RANGE;
+            positions
+                .iter()
+                .map(|a| {
+                    positions
+                        .iter()
+                        .filter(|b| a.distance_squared(**b) < range_sq)
+                        .count()
+                })
+                .sum()
+        });
+        let grid_all = time(|| {
+            grid.build(positions.iter().copied());
+            positions
+                .iter()
+                .map(|a| query_count(&grid, &positions, *a, NEIGHBOUR_RANGE))
+                .sum()
+        });
+
+        println!(
+            "{count:>5} entities | one vs all: brute {brute_point:>9.1?} grid {grid_point:>9.1?} \
+             | all vs all: brute {brute_all:>9.1?} grid {grid_all:>9.1?}"
+        );
+    }
+}
+
+fn query_count(grid: &Grid, positions: &[Vec2], at: Vec2, range: f32) -> usize {
+    let mut n = 0;
+    grid.query(at, range, |i| {
+        if positions[i].distance_squared(at) < range * range {
+            n += 1;
+        }
+    });
+    n
+}
+
+// average time per run of `f`. the result is kept so the work isn't optimised out.
+fn time(mut f: impl FnMut() -> usize) -> std::time::Duration {
+    let start = Instant::now();
+    let mut total = 0;
+    for _ in 0..ITERS {
+        total += f();
+    }
+    std::hint::black_box(total);
+    start.elapsed() / ITERS
+}
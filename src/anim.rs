@@ -0,0 +1,141 @@
+// sprite sheet animation. every sheet has the same layout, one row per clip with the frames running
+// left to right:
+//
+//   idle    4 frames, looping
+//   walk    4 frames, looping
+//   roll    4 frames, looping. only the cook really rolls, other sheets just have something there
+//   death   6 frames, then holds on the last one
+//
+// frames are a third bigger than the sprite in them, so that squashes and tilts don't get cut off.
+use crate::assets;
+use macroquad::prelude::*;
+
+const COLUMNS: f32 = 6.0;
+const ROWS: f32 = 4.0;
+const FRAME_PADDING: f32 = 4.0 / 3.0;
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum Clip {
+    Idle,
+    Walk,
+    Roll,
+    Death,
+}
+
+impl Clip {
+    // how many frames the clip has, and how many ticks each one is shown for
+    fn frames(self) -> (i32, i32) {
+        match self {
+            Clip::Idle => (4, 12),
+            Clip::Walk => (4, 6),
+            Clip::Roll => (4, 4),
+            Clip::Death => (6, 5),
+        }
+    }
+
+    fn row(self) -> f32 {
+        match self {
+            Clip::Idle => 0.0,
+            Clip::Walk => 1.0,
+            Clip::Roll => 2.0,
+            Clip::Death => 3.0,
+        }
+    }
+}
+
+pub struct Sheet {
+    texture: Texture2D,
+    frame_size: Vec2,
+}
+
+impl Sheet {
+    pub fn new(texture: Texture2D) -> Self {
+        Self {
+            texture,
+            frame_size: vec2(texture.width() / COLUMNS, texture.height() / ROWS),
+        }
+    }
+
+    // a `width` by `height` sheet of plain circles, standing in for one that couldn't be loaded
+    pub fn placeholder(width: u16, height: u16, colour: Color) -> Self {
+        let (columns, rows) = (COLUMNS as u16, ROWS as u16);
+        // sprites don't fill their frames, see FRAME_PADDING
+        let fill = 1.0 / FRAME_PADDING;
+        Self::new(assets::circles(width, height, columns, rows, fill, colour))
+    }
+}
+
+// which clip an entity is playing, and how far through it is
+#[derive(Copy, Clone)]
+pub struct Anim {
+    clip: Clip,
+    ticks: i32,
+}
+
+impl Anim {
+    pub fn new(clip: Clip) -> Self {
+        Self { clip, ticks: 0 }
+    }
+
+    // moves on a tick, starting `clip` from the beginning if it isn't already playing.
+    pub fn tick(&mut self, clip: Clip) {
+        if clip != self.clip {
+            *self = Self::new(clip);
+        } else {
+            self.ticks += 1;
+        }
+    }
+
+    // ticks since the current clip started
+    pub fn ticks(&self) -> i32 {
+        self.ticks
+    }
+
+    // whether a death clip has played all the way through. other clips loop, so never finish
+    pub fn finished(&self) -> bool {
+        let (frames, ticks_per_frame) = self.clip.frames();
+        self.clip == Clip::Death && self.ticks >= frames * ticks_per_frame
+    }
+
+    fn frame(&self) -> i32 {
+        let (frames, ticks_per_frame) = self.clip.frames();
+        let frame = self.ticks / ticks_per_frame;
+        if self.clip == Clip::Death {
+            frame.min(frames - 1)
+        } else {
+            frame % frames
+        }
+    }
+}
+
+// draws the current frame of `anim`, with the sprite filling a circle of `radius` around `pos`.
+pub fn draw(
+    sheet: &Sheet,
+    anim: &Anim,
+    pos: Vec2,
+    radius: f32,
+    rotation: f32,
+    flip_x: bool,
+    tint: Color,
+) {
+    let size = vec2(radius, radius) * 2.0 * FRAME_PADDING;
+    let source = Rect::new(
+        anim.frame() as f32 * sheet.frame_size.x,
+        anim.clip.row() * sheet.frame_size.y,
+        sheet.frame_size.x,
+        sheet.frame_size.y,
+    );
+    draw_texture_ex(
+        sheet.texture,
+        pos.x - size.x / 2.0,
+        pos.y - size.y / 2.0,
+        tint,
+        DrawTextureParams {
+            dest_size: Some(size),
+            source: Some(source),
+            rotation,
+            flip_x,
+            ..Default::default()
+        },
+    );
+}
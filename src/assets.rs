@@ -1,40 +1,351 @@
+// everything the game loads. normally these are all built in, but an asset pack (see pack.rs) can
+// replace any of them. anything that can't be loaded doesn't stop the game: sounds are silent and
+// textures are plain circles instead, and what went wrong is shown once loading's done.
+use crate::anim::Sheet;
+use crate::loading;
+use crate::pack::Pack;
 use macroquad::audio::{load_sound_from_bytes, Sound};
 use macroquad::prelude::*;
+use std::borrow::Cow;
+
+// browsers never finish loading sounds they can't decode, see decode_sound
+#[cfg(target_arch = "wasm32")]
+const DECODE_TIMEOUT: f64 = 5.0;
 
 pub struct Assets {
     pub bgm: Sound,
+    pub bgm_calm: Sound,
+    pub heartbeat: Sound,
+    pub game_over: Sound,
     pub roll: Sound,
-    pub enemy_shoot: Sound,
-    pub enemy_death: Sound,
-    pub bread_attack: Sound,
+    pub enemy_shoot: Panned,
+    pub enemy_death: Panned,
+    pub bread_attack: Panned,
 
     pub background: Texture2D,
-    pub player: Texture2D,
+    pub player: Sheet,
     pub player_weapon: Texture2D,
-    pub lemon: Texture2D,
-    pub grape: Texture2D,
+    pub lime: Sheet,
+    pub lemon: Sheet,
+    pub grape: Sheet,
     pub bullet: Texture2D,
-    pub bread: Texture2D,
+    pub bread: Sheet,
+
+    pub font: Font,
+
+    // files the asset pack didn't have, that came from the built in ones instead
+    pub missing: Vec<&'static str>,
+    // everything that couldn't be loaded, and why
+    pub errors: Vec<String>,
 }
 
-pub async fn load() -> Result<Assets, FileError> {
+// a sound in two halves, one with just the left channel and one with just the right. there's no
+// way to pan a sound as it's played, but playing both halves at different volumes does the same
+pub struct Panned {
+    pub left: Sound,
+    pub right: Sound,
+}
+
+macro_rules! built_in {
+    ($name:literal) => {
+        ($name, include_bytes!($name))
+    };
+}
+
+// every asset file, by the name it has in an asset pack
+pub const BUILT_IN: &[(&str, &[u8])] = &[
     // ogg exported from audacity seems to work well.
-    // Texture2D::from_file_with_format(bytes, format)
-    Ok(Assets {
-        bgm: load_sound_from_bytes(include_bytes!("radmusic.ogg")).await?,
-        roll: load_sound_from_bytes(include_bytes!("60013__qubodup__whoosh.ogg")).await?,
-        enemy_shoot: load_sound_from_bytes(include_bytes!("245645__unfa__cartoon-pop-clean.ogg"))
-            .await?,
-        enemy_death: load_sound_from_bytes(include_bytes!("232135__yottasounds__splat-005.ogg"))
-            .await?,
-        bread_attack: load_sound_from_bytes(include_bytes!("376860_6886013-lq.ogg")).await?,
-
-        background: Texture2D::from_file_with_format(include_bytes!("background.png"), None),
-        player: Texture2D::from_file_with_format(include_bytes!("cook.png"), None),
-        player_weapon: Texture2D::from_file_with_format(include_bytes!("playerweapon.png"), None),
-        lemon: Texture2D::from_file_with_format(include_bytes!("lemon.png"), None),
-        grape: Texture2D::from_file_with_format(include_bytes!("grape.png"), None),
-        bullet: Texture2D::from_file_with_format(include_bytes!("strawberry.png"), None),
-        bread: Texture2D::from_file_with_format(include_bytes!("bread.png"), None),
+    built_in!("radmusic.ogg"),
+    // music layers, see audio.rs. music_calm is radmusic low passed and mixed down to mono at a
+    // quarter of the rate, so it's exactly as long and loops along with it. the heartbeat and game
+    // over sting are synthesised
+    built_in!("music_calm.ogg"),
+    built_in!("heartbeat.ogg"),
+    built_in!("game_over.ogg"),
+    built_in!("60013__qubodup__whoosh.ogg"),
+    // mixed down to mono and split in to halves from freesound.org sounds 245645 (cartoon pop clean,
+    // by unfa), 232135 (splat 005, by yottasounds) and 376860 (by MilanKovanda), see LICENSE
+    built_in!("enemy_shoot_left.ogg"),
+    built_in!("enemy_shoot_right.ogg"),
+    built_in!("enemy_death_left.ogg"),
+    built_in!("enemy_death_right.ogg"),
+    built_in!("bread_attack_left.ogg"),
+    built_in!("bread_attack_right.ogg"),
+    built_in!("background.png"),
+    // sheets are made from cook.png etc, see anim.rs for the layout
+    built_in!("cook_sheet.png"),
+    built_in!("playerweapon.png"),
+    built_in!("lime_sheet.png"),
+    built_in!("lemon_sheet.png"),
+    built_in!("grape_sheet.png"),
+    built_in!("strawberry.png"),
+    built_in!("bread_sheet.png"),
+    built_in!("DejaVuSans-Bold.ttf"),
+];
+
+// loads everything, from `pack` where it has the file, showing how far it's got on the loading screen
+pub async fn load(pack: Option<&Pack>) -> Assets {
+    let mut l = Loader {
+        pack,
+        loaded: 0,
+        missing: Vec::new(),
+        errors: Vec::new(),
+    };
+    let mut ass = Assets {
+        bgm: l.sound("radmusic.ogg").await,
+        bgm_calm: l.sound("music_calm.ogg").await,
+        heartbeat: l.sound("heartbeat.ogg").await,
+        game_over: l.sound("game_over.ogg").await,
+        roll: l.sound("60013__qubodup__whoosh.ogg").await,
+        enemy_shoot: l
+            .panned("enemy_shoot_left.ogg", "enemy_shoot_right.ogg")
+            .await,
+        enemy_death: l
+            .panned("enemy_death_left.ogg", "enemy_death_right.ogg")
+            .await,
+        bread_attack: l
+            .panned("bread_attack_left.ogg", "bread_attack_right.ogg")
+            .await,
+
+        background: l.texture("background.png", DARKGRAY).await,
+        player: l.sheet("cook_sheet.png", SKYBLUE).await,
+        player_weapon: l.texture("playerweapon.png", LIGHTGRAY).await,
+        lime: l.sheet("lime_sheet.png", GREEN).await,
+        lemon: l.sheet("lemon_sheet.png", YELLOW).await,
+        grape: l.sheet("grape_sheet.png", PURPLE).await,
+        bullet: l.texture("strawberry.png", RED).await,
+        bread: l.sheet("bread_sheet.png", ORANGE).await,
+
+        font: l.font("DejaVuSans-Bold.ttf").await,
+
+        missing: l.missing,
+        errors: l.errors,
+    };
+
+    // almost certainly the wrong directory, or files that have been renamed. which files they
+    // should be is in the missing list
+    if ass.missing.len() == BUILT_IN.len() {
+        let e = "the asset pack doesn't have any of the game's files. is it the right directory?";
+        ass.errors.push(e.to_string());
+    }
+    ass
+}
+
+// macroquad panics on sounds it can't decode, so they're decoded here instead, and handed over as a
+// wav, which is quick for it to read
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn decode_sound(bytes: &[u8]) -> Result<Sound, String> {
+    let mut reader = audrey::Reader::new(std::io::Cursor::new(bytes)).map_err(|e| e.to_string())?;
+    let description = reader.description();
+    let channels = description.channel_count() as u16;
+    if channels > 2 {
+        return Err(format!(
+            "it has {} channels, only mono and stereo are supported",
+            channels
+        ));
+    }
+    let samples = reader
+        .samples::<f32>()
+        .collect::<Result<Vec<f32>, _>>()
+        .map_err(|e| e.to_string())?;
+    let wav = wav(&samples, channels, description.sample_rate());
+    load_sound_from_bytes(&wav).await.map_err(|e| e.to_string())
+}
+
+// browsers decode sounds in the background, and ones they can't decode never finish loading rather
+// than failing, so this gives up on them after a while. some browsers can't do ogg at all
+#[cfg(target_arch = "wasm32")]
+pub async fn decode_sound(bytes: &[u8]) -> Result<Sound, String> {
+    use std::future::Future;
+    use std::task::Poll;
+
+    let give_up = get_time() + DECODE_TIMEOUT;
+    let mut load = Box::pin(load_sound_from_bytes(bytes));
+    std::future::poll_fn(|cx| match load.as_mut().poll(cx) {
+        Poll::Ready(sound) => Poll::Ready(sound.map_err(|e| e.to_string())),
+        Poll::Pending if get_time() > give_up => {
+            Poll::Ready(Err("the browser couldn't decode it".to_string()))
+        }
+        Poll::Pending => Poll::Pending,
     })
+    .await
+}
+
+// macroquad panics on images it can't decode, so they're decoded here instead
+pub fn decode_texture(bytes: &[u8]) -> Result<Texture2D, String> {
+    let image = image::load_from_memory(bytes)
+        .map_err(|e| e.to_string())?
+        .to_rgba8();
+    Ok(Texture2D::from_rgba8(
+        image.width() as u16,
+        image.height() as u16,
+        &image.into_raw(),
+    ))
+}
+
+// `width` by `height`, split in to `columns` by `rows` cells with a circle of `colour` in each,
+// `fill` of the way across it. stands in for textures that couldn't be loaded
+pub fn circles(
+    width: u16,
+    height: u16,
+    columns: u16,
+    rows: u16,
+    fill: f32,
+    colour: Color,
+) -> Texture2D {
+    let mut image = Image::gen_image_color(width, height, BLANK);
+    let cell = vec2(width as f32 / columns as f32, height as f32 / rows as f32);
+    let radius = cell.min_element() / 2.0 * fill;
+    for y in 0..height {
+        for x in 0..width {
+            let in_cell = vec2((x as f32 + 0.5) % cell.x, (y as f32 + 0.5) % cell.y);
+            if in_cell.distance(cell / 2.0) < radius {
+                image.set_pixel(x as u32, y as u32, colour);
+            }
+        }
+    }
+    Texture2D::from_image(&image)
+}
+
+fn built_in(name: &str) -> &'static [u8] {
+    let (_, bytes) = BUILT_IN
+        .iter()
+        .find(|(built_in, _)| *built_in == name)
+        .expect("not a built in asset");
+    bytes
+}
+
+// the size of a built in png, from its header, so placeholders can be the same size as what they
+// stand in for
+fn built_in_size(name: &str) -> (u16, u16) {
+    let header = &built_in(name)[16..24];
+    let width = u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+    let height = u32::from_be_bytes([header[4], header[5], header[6], header[7]]);
+    (width as u16, height as u16)
+}
+
+// a 16 bit wav file of `samples`, with `channels` interleaved
+fn wav(samples: &[f32], channels: u16, rate: u32) -> Vec<u8> {
+    let data_len = samples.len() as u32 * 2;
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    wav.extend(b"RIFF");
+    wav.extend((36 + data_len).to_le_bytes());
+    wav.extend(b"WAVEfmt ");
+    // 16 bytes of format: pcm, the channels, the rate, bytes per second, bytes per frame and bits
+    // per sample
+    wav.extend(16u32.to_le_bytes());
+    wav.extend(1u16.to_le_bytes());
+    wav.extend(channels.to_le_bytes());
+    wav.extend(rate.to_le_bytes());
+    wav.extend((rate * channels as u32 * 2).to_le_bytes());
+    wav.extend((channels * 2).to_le_bytes());
+    wav.extend(16u16.to_le_bytes());
+    wav.extend(b"data");
+    wav.extend(data_len.to_le_bytes());
+    for sample in samples {
+        let sample = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        wav.extend(sample.to_le_bytes());
+    }
+    wav
+}
+
+struct Loader<'a> {
+    pack: Option<&'a Pack>,
+    // how many files have been loaded so far, for the loading screen
+    loaded: usize,
+    missing: Vec<&'static str>,
+    errors: Vec<String>,
+}
+
+impl Loader<'_> {
+    async fn bytes(&mut self, name: &'static str) -> Cow<'static, [u8]> {
+        // give the loading screen a frame, so it's up to date while this file's loading
+        loading::draw(self.loaded, BUILT_IN.len(), name);
+        next_frame().await;
+        self.loaded += 1;
+
+        if let Some(pack) = self.pack {
+            if let Some(bytes) = pack.get(name).await {
+                return Cow::Owned(bytes);
+            }
+            self.missing.push(name);
+        }
+        Cow::Borrowed(built_in(name))
+    }
+
+    async fn sound(&mut self, name: &'static str) -> Sound {
+        let bytes = self.bytes(name).await;
+        match decode_sound(&bytes).await {
+            Ok(sound) => sound,
+            Err(e) => {
+                self.errors.push(format!("{}: {}", name, e));
+                // a tenth of a second of nothing
+                let silence = wav(&[0.0; 4410], 1, 44100);
+                load_sound_from_bytes(&silence)
+                    .await
+                    .expect("couldn't load silence")
+            }
+        }
+    }
+
+    async fn panned(&mut self, left: &'static str, right: &'static str) -> Panned {
+        Panned {
+            left: self.sound(left).await,
+            right: self.sound(right).await,
+        }
+    }
+
+    // the texture, or if it can't be loaded, the size to make a placeholder for it
+    async fn decode(&mut self, name: &'static str) -> Result<Texture2D, (u16, u16)> {
+        let bytes = self.bytes(name).await;
+        decode_texture(&bytes).map_err(|e| {
+            self.errors.push(format!("{}: {}", name, e));
+            built_in_size(name)
+        })
+    }
+
+    async fn texture(&mut self, name: &'static str, placeholder: Color) -> Texture2D {
+        self.decode(name)
+            .await
+            .unwrap_or_else(|(width, height)| circles(width, height, 1, 1, 1.0, placeholder))
+    }
+
+    async fn sheet(&mut self, name: &'static str, placeholder: Color) -> Sheet {
+        self.decode(name)
+            .await
+            .map(Sheet::new)
+            .unwrap_or_else(|(width, height)| Sheet::placeholder(width, height, placeholder))
+    }
+
+    // a broken font falls back to the built in one, there's no sensible placeholder for text
+    async fn font(&mut self, name: &'static str) -> Font {
+        let bytes = self.bytes(name).await;
+        load_ttf_font_from_bytes(&bytes).unwrap_or_else(|e| {
+            self.errors.push(format!("{}: {}", name, e));
+            load_ttf_font_from_bytes(built_in(name)).expect("the built in font is broken")
+        })
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    // a lot of these are generated rather than exported from an audio editor, so check every one
+    // decodes in full
+    #[test]
+    fn built_in_sounds_decode() {
+        let sounds = BUILT_IN
+            .iter()
+            .filter(|(name, _)| name.ends_with(".ogg") || name.ends_with(".wav"));
+        for (name, bytes) in sounds {
+            let mut reader = audrey::Reader::new(std::io::Cursor::new(bytes))
+                .unwrap_or_else(|e| panic!("{}: {}", name, e));
+            let samples = reader
+                .samples::<f32>()
+                .collect::<Result<Vec<f32>, _>>()
+                .unwrap_or_else(|e| panic!("{}: {}", name, e));
+            assert!(!samples.is_empty(), "{} is empty", name);
+        }
+    }
 }
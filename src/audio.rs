@@ -0,0 +1,217 @@
+// plays the music and sound effects, at the volumes in the settings. sound effects are kicked off
+// from events in the game state, after each tick.
+//
+// the music is in layers that all loop together, faded in and out with how intense the game is:
+//
+//   calm    a muffled copy of the track, for when there isn't much going on
+//   full    the track itself, taking over as the kitchen fills up
+//   danger  a heartbeat, when something's about to hit the player
+//
+// and the game over sting plays over the top when the player dies, while the rest fades out.
+use crate::assets::{Assets, Panned};
+use crate::settings::Settings;
+use macroquad::audio::{play_sound, set_sound_volume, PlaySoundParams, Sound};
+use macroquad::prelude::*;
+
+// the music's mixed well under the sound effects, even at full volume
+const MUSIC_LEVEL: f32 = 0.125;
+const STING_LEVEL: f32 = 0.3;
+// how many enemies it takes to bring the full track all the way in
+const BUSY_ENEMIES: f32 = 12.0;
+// how much the rest of the music drops while the heartbeat's playing
+const DANGER_DUCK: f32 = 0.5;
+// sound effects are panned all the way to one side by the edge of the view, and get quieter the
+// further away they are, down to QUIETEST
+const PAN_WIDTH: f32 = crate::VIEW_WIDTH / 2.0;
+const FULL_VOLUME_WITHIN: f32 = 200.0;
+const FADE_DISTANCE: f32 = 1000.0;
+const QUIETEST: f32 = 0.25;
+// how much of the way from silent to full volume a layer fades in a second
+const LAYER_FADE_PER_SEC: f32 = 0.6;
+const LAYER_COUNT: usize = 3;
+
+#[derive(Copy, Clone)]
+pub enum Sfx {
+    Roll,
+    EnemyShoot,
+    EnemyDeath,
+    BreadAttack,
+}
+
+const SFX_COUNT: usize = 4;
+
+// the player makes the sounds that aren't panned, so they're always in the middle
+enum SfxSound<'a> {
+    Centred(Sound),
+    Panned(&'a Panned),
+}
+
+impl Sfx {
+    fn sound(self, ass: &Assets) -> SfxSound<'_> {
+        match self {
+            Sfx::Roll => SfxSound::Centred(ass.roll),
+            Sfx::EnemyShoot => SfxSound::Panned(&ass.enemy_shoot),
+            Sfx::EnemyDeath => SfxSound::Panned(&ass.enemy_death),
+            Sfx::BreadAttack => SfxSound::Panned(&ass.bread_attack),
+        }
+    }
+
+    // how many copies can play over each other, and about how long each one lasts in seconds.
+    // there's no way to ask when a sound's finished, so this just counts the ones started recently
+    fn voices(self) -> (usize, f64) {
+        match self {
+            Sfx::Roll => (1, 0.45),
+            Sfx::EnemyShoot => (3, 0.17),
+            Sfx::EnemyDeath => (3, 0.15),
+            Sfx::BreadAttack => (2, 3.15),
+        }
+    }
+}
+
+// what's going on in the game, for picking which music layers to play
+pub struct Intensity {
+    pub enemies: usize,
+    // breads are the nearest thing to a boss, so bring everything in whenever there's one about
+    pub heavy: bool,
+    // from 0 to 1, how close the player is to being hit. one hit's all it takes
+    pub danger: f32,
+    pub game_over: bool,
+}
+
+struct Layer {
+    // how loud the layer is at the moment, and how loud it's fading towards, from 0 to 1
+    level: f32,
+    target: f32,
+    // what it was last actually set to, after the settings
+    volume: f32,
+}
+
+pub struct Mixer {
+    // when each copy of a sound effect that might still be playing was started
+    voices: [Vec<f64>; SFX_COUNT],
+    // calm, full and danger, in that order
+    layers: [Layer; LAYER_COUNT],
+}
+
+impl Mixer {
+    pub fn new() -> Self {
+        let layer = |level| Layer {
+            level,
+            target: level,
+            volume: 0.0,
+        };
+        Self {
+            voices: Default::default(),
+            layers: [layer(1.0), layer(0.0), layer(0.0)],
+        }
+    }
+
+    // starts every layer at once, so they stay in time. quiet ones just play silently
+    pub fn start_music(&mut self, ass: &Assets, settings: &Settings) {
+        for (layer, sound) in self.layers.iter_mut().zip(layer_sounds(ass)) {
+            layer.volume = layer.level * settings.music_volume() * MUSIC_LEVEL;
+            play_sound(
+                sound,
+                PlaySoundParams {
+                    looped: true,
+                    volume: layer.volume,
+                },
+            );
+        }
+    }
+
+    pub fn set_intensity(&mut self, intensity: &Intensity) {
+        let targets = if intensity.game_over {
+            [0.0; LAYER_COUNT]
+        } else {
+            let busy = if intensity.heavy {
+                1.0
+            } else {
+                (intensity.enemies as f32 / BUSY_ENEMIES).min(1.0)
+            };
+            let duck = 1.0 - intensity.danger * DANGER_DUCK;
+            // equal power crossfade, so it doesn't dip in the middle
+            [
+                (1.0 - busy).sqrt() * duck,
+                busy.sqrt() * duck,
+                intensity.danger,
+            ]
+        };
+        for (layer, target) in self.layers.iter_mut().zip(targets) {
+            layer.target = target;
+        }
+    }
+
+    // plays the sting over whatever music's left
+    pub fn game_over(&mut self, ass: &Assets, settings: &Settings) {
+        let volume = settings.music_volume() * STING_LEVEL;
+        if volume > 0.0 {
+            play_sound(
+                ass.game_over,
+                PlaySoundParams {
+                    looped: false,
+                    volume,
+                },
+            );
+        }
+    }
+
+    // call every frame, to fade the layers, and so volume changes in the settings are heard
+    // straight away
+    pub fn update(&mut self, ass: &Assets, settings: &Settings, dt: f32) {
+        let step = LAYER_FADE_PER_SEC * dt;
+        for (layer, sound) in self.layers.iter_mut().zip(layer_sounds(ass)) {
+            layer.level += (layer.target - layer.level).clamp(-step, step);
+            let volume = layer.level * settings.music_volume() * MUSIC_LEVEL;
+            if volume != layer.volume {
+                layer.volume = volume;
+                set_sound_volume(sound, volume);
+            }
+        }
+    }
+
+    // plays a sound effect, unless too many copies of it are playing already. `offset` is where it
+    // came from, relative to the player
+    pub fn play(&mut self, ass: &Assets, settings: &Settings, sfx: Sfx, offset: Vec2) {
+        let volume = settings.sfx_volume();
+        if volume <= 0.0 {
+            return;
+        }
+
+        let now = get_time();
+        let (max, length) = sfx.voices();
+        let voices = &mut self.voices[sfx as usize];
+        voices.retain(|&started| now - started < length);
+        // copies started on the same frame would just sound like one louder one
+        if voices.len() >= max || voices.last() == Some(&now) {
+            return;
+        }
+        voices.push(now);
+
+        match sfx.sound(ass) {
+            SfxSound::Centred(sound) => play_once(sound, volume),
+            SfxSound::Panned(sound) => {
+                let distance = offset.length() - FULL_VOLUME_WITHIN;
+                let volume = volume * (1.0 - distance / FADE_DISTANCE).clamp(QUIETEST, 1.0);
+                // in the middle, both halves play at full volume, same as the whole sound would
+                let pan = (offset.x / PAN_WIDTH).clamp(-1.0, 1.0);
+                play_once(sound.left, volume * (1.0 - pan).min(1.0));
+                play_once(sound.right, volume * (1.0 + pan).min(1.0));
+            }
+        }
+    }
+}
+
+fn play_once(sound: Sound, volume: f32) {
+    play_sound(
+        sound,
+        PlaySoundParams {
+            looped: false,
+            volume,
+        },
+    );
+}
+
+fn layer_sounds(ass: &Assets) -> [Sound; LAYER_COUNT] {
+    [ass.bgm_calm, ass.bgm, ass.heartbeat]
+}
@@ -0,0 +1,248 @@
+// camera that follows the player around arenas bigger than the screen, plus markers for anything
+// that's off screen, and effects on top of it to make hits feel harder.
+use crate::settings::Settings;
+use crate::{VIEW_HEIGHT, VIEW_WIDTH};
+use macroquad::prelude::*;
+
+// how quickly the camera catches up with the player. higher is snappier
+const FOLLOW_RATE: f32 = 6.0;
+// how far in from the edge of the screen off-screen markers are drawn
+const MARKER_INSET: f32 = 16.0;
+const MARKER_SIZE: f32 = 10.0;
+
+// shake is trauma squared, so small knocks barely move the camera and big ones really throw it
+const TRAUMA_DECAY_PER_SEC: f32 = 1.5;
+const SHAKE_OFFSET_MAX: f32 = 14.0;
+const SHAKE_DEGREES_MAX: f32 = 2.5;
+// how long the game freezes for each thing killed on the same tick, once there's more than one
+const HIT_STOP_PER_KILL: f32 = 0.03;
+const HIT_STOP_MAX: f32 = 0.12;
+// after the player dies, time slows right down then recovers, while the camera zooms in on them
+const DEATH_SLOWMO_SECS: f32 = 1.2;
+const DEATH_SLOWEST: f32 = 0.2;
+const DEATH_ZOOM_SECS: f32 = 0.8;
+const DEATH_ZOOM: f32 = 1.6;
+
+pub struct FollowCamera {
+    centre: Vec2,
+}
+
+impl FollowCamera {
+    pub fn new(target: Vec2, world_size: Vec2) -> Self {
+        let mut cam = Self { centre: target };
+        cam.keep_in_world(world_size);
+        cam
+    }
+
+    // eases towards `target`, frame rate independently.
+    pub fn update(&mut self, target: Vec2, world_size: Vec2, dt: f32) {
+        let t = 1.0 - (-FOLLOW_RATE * dt).exp();
+        self.centre += (target - self.centre) * t;
+        self.keep_in_world(world_size);
+    }
+
+    // stops the view going off the edge of the world. worlds smaller than the view are centred.
+    fn keep_in_world(&mut self, world_size: Vec2) {
+        let half_view = vec2(VIEW_WIDTH, VIEW_HEIGHT) / 2.0;
+        self.centre.x = if world_size.x <= VIEW_WIDTH {
+            world_size.x / 2.0
+        } else {
+            self.centre.x.clamp(half_view.x, world_size.x - half_view.x)
+        };
+        self.centre.y = if world_size.y <= VIEW_HEIGHT {
+            world_size.y / 2.0
+        } else {
+            self.centre.y.clamp(half_view.y, world_size.y - half_view.y)
+        };
+    }
+
+    // the part of the world that's visible
+    pub fn view_rect(&self) -> Rect {
+        Rect::new(
+            self.centre.x - VIEW_WIDTH / 2.0,
+            self.centre.y - VIEW_HEIGHT / 2.0,
+            VIEW_WIDTH,
+            VIEW_HEIGHT,
+        )
+    }
+
+    pub fn world_camera(&self) -> Camera2D {
+        letterboxed(self.view_rect())
+    }
+
+    // where a point in the window, like the mouse, is in the world. Camera2D::screen_to_world doesn't
+    // know about the letterboxing. only the console uses it, which is only in debug builds
+    #[cfg(debug_assertions)]
+    pub fn screen_to_world(&self, point: Vec2) -> Vec2 {
+        let (x, y, width, height) = viewport();
+        let in_view = (point - vec2(x as f32, y as f32)) / vec2(width as f32, height as f32);
+        let view = self.view_rect();
+        view.point() + in_view * view.size()
+    }
+}
+
+// fixed camera for drawing the HUD, in view sized pixels regardless of where the world camera is.
+pub fn hud_camera() -> Camera2D {
+    letterboxed(Rect::new(0.0, 0.0, VIEW_WIDTH, VIEW_HEIGHT))
+}
+
+// how many window pixels there are to each view pixel
+pub fn view_scale() -> f32 {
+    (screen_width() / VIEW_WIDTH).min(screen_height() / VIEW_HEIGHT)
+}
+
+fn letterboxed(rect: Rect) -> Camera2D {
+    let mut cam = Camera2D::from_display_rect(rect);
+    cam.viewport = Some(viewport());
+    cam
+}
+
+// the part of the window the view's drawn to, as x, y, width and height
+fn viewport() -> (i32, i32, i32, i32) {
+    // FUTURE: set viewport to maintain a constant aspect ratio, rather than stretching.
+    const TARGET_ASPECT: f32 = VIEW_WIDTH / VIEW_HEIGHT;
+    let window_aspect = screen_width() / screen_height();
+    if window_aspect < TARGET_ASPECT {
+        // window is squarer than we want, need to add borders at top and bottom
+        let sw = screen_width();
+        let sh = sw / TARGET_ASPECT;
+        let excess_height = (screen_height() - sh) as i32;
+        (0, excess_height / 2, sw as i32, sh as i32)
+    } else {
+        // window is wider than we want. add borders at side
+        let sh = screen_height();
+        let sw = sh * TARGET_ASPECT;
+        let excess_width = (screen_width() - sw) as i32;
+        (excess_width / 2, 0, sw as i32, sh as i32)
+    }
+}
+
+// draws an arrow just inside the edge of `view`, pointing at `target`, if `target` is off screen.
+// needs the world camera to be active.
+pub fn draw_edge_marker(view: Rect, target: Vec2, colour: Color) {
+    if view.contains(target) {
+        return;
+    }
+
+    let centre = vec2(view.x + view.w / 2.0, view.y + view.h / 2.0);
+    let dir = target - centre;
+    let half = vec2(view.w / 2.0, view.h / 2.0) - vec2(MARKER_INSET, MARKER_INSET);
+
+    // scale the direction down until it hits the inset edge of the view
+    let scale_x = if dir.x != 0.0 {
+        half.x / dir.x.abs()
+    } else {
+        f32::MAX
+    };
+    let scale_y = if dir.y != 0.0 {
+        half.y / dir.y.abs()
+    } else {
+        f32::MAX
+    };
+    let at = centre + dir * scale_x.min(scale_y);
+
+    let fwd = dir.normalize_or_zero();
+    let side = fwd.perp();
+    draw_triangle(
+        at + fwd * MARKER_SIZE,
+        at - fwd * MARKER_SIZE * 0.6 + side * MARKER_SIZE * 0.7,
+        at - fwd * MARKER_SIZE * 0.6 - side * MARKER_SIZE * 0.7,
+        colour,
+    );
+}
+
+// screen shake, hit-stop and the slow motion zoom when the player dies. all of them run in real
+// time, and are scaled down by the player's settings.
+pub struct Juice {
+    // 0 to 1
+    trauma: f32,
+    // seconds of freeze left
+    hit_stop: f32,
+    // where the player died, and seconds since
+    death: Option<(Vec2, f32)>,
+    // keeps the shake moving
+    time: f32,
+}
+
+impl Juice {
+    pub fn new() -> Self {
+        Self {
+            trauma: 0.0,
+            hit_stop: 0.0,
+            death: None,
+            time: 0.0,
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.time += dt;
+        self.trauma = (self.trauma - TRAUMA_DECAY_PER_SEC * dt).max(0.0);
+        self.hit_stop = (self.hit_stop - dt).max(0.0);
+        if let Some((_, since)) = &mut self.death {
+            *since += dt;
+        }
+    }
+
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).min(1.0);
+    }
+
+    // freezes the game briefly when a single swipe gets several things at once
+    pub fn multi_kill(&mut self, kills: usize, settings: &Settings) {
+        if kills < 2 {
+            return;
+        }
+        let secs = (kills as f32 * HIT_STOP_PER_KILL).min(HIT_STOP_MAX) * settings.hit_stop;
+        self.hit_stop = self.hit_stop.max(secs);
+    }
+
+    pub fn player_died(&mut self, pos: Vec2) {
+        self.death = Some((pos, 0.0));
+    }
+
+    // back to normal, after a restart
+    pub fn player_alive(&mut self) {
+        self.death = None;
+    }
+
+    // how fast game time should pass, compared to real time
+    pub fn time_scale(&self, settings: &Settings) -> f32 {
+        if self.hit_stop > 0.0 {
+            return 0.0;
+        }
+        match self.death {
+            Some((_, since)) if since < DEATH_SLOWMO_SECS => {
+                let slowest = 1.0 - (1.0 - DEATH_SLOWEST) * settings.death_zoom;
+                let t = since / DEATH_SLOWMO_SECS;
+                slowest + (1.0 - slowest) * t * t
+            }
+            _ => 1.0,
+        }
+    }
+
+    pub fn apply(&self, cam: Camera2D, settings: &Settings) -> Camera2D {
+        let mut cam = cam;
+
+        if let Some((pos, since)) = self.death {
+            // ease in, and stay zoomed until the restart
+            let t = (since / DEATH_ZOOM_SECS).min(1.0);
+            let t = t * t * (3.0 - 2.0 * t) * settings.death_zoom;
+            cam.target = cam.target.lerp(pos, t);
+            cam.zoom *= 1.0 + (DEATH_ZOOM - 1.0) * t;
+        }
+
+        let shake = self.trauma * self.trauma * settings.shake;
+        if shake > 0.0 {
+            // a few sine waves at odd frequencies, rough enough to look random
+            let t = self.time;
+            let wobble = vec2(
+                (t * 37.0).sin() + (t * 21.3).sin() * 0.5,
+                (t * 29.1).cos() + (t * 17.7).sin() * 0.5,
+            ) / 1.5;
+            cam.target += wobble * SHAKE_OFFSET_MAX * shake;
+            cam.rotation += (t * 23.0).sin() * SHAKE_DEGREES_MAX * shake;
+        }
+
+        cam
+    }
+}
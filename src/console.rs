@@ -0,0 +1,349 @@
+// developer console, dropped down with the key under escape. commands cheat and spawn things, for
+// setting up situations to test. the game's paused while it's open. only in debug builds.
+use crate::camera::{self, FollowCamera};
+use crate::hud::{self, Anchor, Layout};
+use crate::{
+    patterns, waves, BreadAim, GameState, PlayerState, BREAD_RADIUS, GRAPE_RADIUS, LEMON_RADIUS,
+    VIEW_WIDTH,
+};
+use macroquad::prelude::*;
+use std::fmt::Write;
+
+const TOGGLE: KeyCode = KeyCode::GraveAccent;
+const LINES_SHOWN: usize = 8;
+// enough for a dump of a normal game with everything at its cap
+const LINES_KEPT: usize = 2000;
+const TEXT_SIZE: f32 = 16.0;
+// fits LINES_SHOWN lines and the input, as laid out by hud::Layout
+const HEIGHT: f32 = 240.0;
+const TIME_SCALE_MAX: f32 = 10.0;
+
+const HELP: &[&str] = &[
+    "spawn lemon|grape|bread [count] [pattern or aim]: spawns at the mouse",
+    "wave <number>: starts that wave now",
+    "god: toggles not dying",
+    "timescale <scale>: speeds up or slows down the game",
+    "seed <number>: seeds the random numbers, and restarts",
+    "dump: prints the game state",
+    "page up and down scroll back through what's been said",
+];
+
+pub struct Console {
+    pub open: bool,
+    // how fast the game runs, compared to normal
+    pub time_scale: f32,
+    input: String,
+    // commands and what they said back, oldest first
+    lines: Vec<(String, Color)>,
+    // how many lines back from the newest the view's scrolled
+    scrolled: usize,
+    // commands run so far, for going back through with up and down. `browsing` is how far back
+    history: Vec<String>,
+    browsing: usize,
+}
+
+impl Console {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            time_scale: 1.0,
+            input: String::new(),
+            lines: vec![("help for a list of commands".to_string(), GRAY)],
+            scrolled: 0,
+            history: Vec::new(),
+            browsing: 0,
+        }
+    }
+
+    // call every frame, before the game ticks. `cam` is for finding where the mouse is
+    pub fn update(&mut self, state: &mut GameState, cam: &FollowCamera) {
+        // macroquad hands back typed characters newest first, and keeps them until they're taken
+        let mut typed = Vec::new();
+        while let Some(c) = get_char_pressed() {
+            typed.push(c);
+        }
+        typed.reverse();
+
+        if is_key_pressed(TOGGLE) {
+            self.open = !self.open;
+            return;
+        }
+        if !self.open {
+            return;
+        }
+
+        self.input
+            .extend(typed.into_iter().filter(|c| !c.is_control() && *c != '`'));
+        if is_key_pressed(KeyCode::Backspace) {
+            self.input.pop();
+        }
+        if is_key_pressed(KeyCode::Up) && self.browsing < self.history.len() {
+            self.browsing += 1;
+            self.input = self.history[self.history.len() - self.browsing].clone();
+        }
+        if is_key_pressed(KeyCode::Down) && self.browsing > 0 {
+            self.browsing -= 1;
+            self.input = match self.browsing {
+                0 => String::new(),
+                back => self.history[self.history.len() - back].clone(),
+            };
+        }
+        if is_key_pressed(KeyCode::PageUp) {
+            let most = self.lines.len().saturating_sub(LINES_SHOWN);
+            self.scrolled = (self.scrolled + LINES_SHOWN).min(most);
+        }
+        if is_key_pressed(KeyCode::PageDown) {
+            self.scrolled = self.scrolled.saturating_sub(LINES_SHOWN);
+        }
+        if is_key_pressed(KeyCode::Enter) && !self.input.trim().is_empty() {
+            let command = std::mem::take(&mut self.input);
+            self.say(format!("> {}", command), WHITE);
+            match self.run(&command, state, cam) {
+                Ok(said) => {
+                    for line in said {
+                        self.say(line, LIGHTGRAY);
+                    }
+                }
+                Err(e) => self.say(e, ORANGE),
+            }
+            self.history.push(command);
+            self.browsing = 0;
+        }
+    }
+
+    fn say(&mut self, line: String, colour: Color) {
+        self.lines.push((line, colour));
+        if self.lines.len() > LINES_KEPT {
+            self.lines.remove(0);
+        }
+        self.scrolled = 0;
+    }
+
+    // what to say back, if it worked
+    fn run(
+        &mut self,
+        command: &str,
+        state: &mut GameState,
+        cam: &FollowCamera,
+    ) -> Result<Vec<String>, String> {
+        let mut words = command.split_whitespace();
+        let name = words.next().unwrap_or("");
+        let mut arg = |what: &str| {
+            words
+                .next()
+                .ok_or_else(|| format!("{} needs {}", name, what))
+        };
+
+        let said = match name {
+            "help" => HELP.iter().map(|line| line.to_string()).collect(),
+            "spawn" => {
+                let what = arg("lemon, grape or bread")?;
+                let count: usize = match arg("") {
+                    Ok(count) => number(count)?,
+                    Err(_) => 1,
+                };
+                let (radius, room) = match what {
+                    "lemon" | "lemons" => (LEMON_RADIUS, state.limits.lemons - state.lemons.len()),
+                    "grape" | "grapes" => (GRAPE_RADIUS, state.limits.grapes - state.grapes.len()),
+                    "bread" | "breads" => (BREAD_RADIUS, state.limits.breads - state.breads.len()),
+                    _ => return Err(format!("can't spawn '{}'", what)),
+                };
+                // the same caps as waves, applied up front so a huge count doesn't loop for ages
+                let spawning = count.min(room);
+                let mut at = cam.screen_to_world(mouse_position().into());
+                state.level.push_out(&mut at, radius);
+                state.level.keep_in_bounds(&mut at);
+
+                match what {
+                    "lemon" | "lemons" => {
+                        for _ in 0..spawning {
+                            state.spawn_lemon(Some(at));
+                        }
+                    }
+                    "grape" | "grapes" => {
+                        let name = arg("").unwrap_or("aimed");
+                        let name = patterns::name(name)
+                            .ok_or_else(|| format!("there's no pattern called '{}'", name))?;
+                        for _ in 0..spawning {
+                            state.spawn_grape(patterns::get(name), Some(at));
+                        }
+                    }
+                    _ => {
+                        let aim = match arg("") {
+                            Ok(name) => waves::bread_aim(name)
+                                .ok_or_else(|| format!("'{}' isn't a way to aim", name))?,
+                            Err(_) => BreadAim::LockOnWarn,
+                        };
+                        for _ in 0..spawning {
+                            state.spawn_bread(aim, Some(at));
+                        }
+                    }
+                }
+                if spawning < count {
+                    vec![format!(
+                        "spawned {}, the rest would go over the cap",
+                        spawning
+                    )]
+                } else {
+                    vec![format!("spawned {}", spawning)]
+                }
+            }
+            "wave" => {
+                let wave: i32 = number(arg("a wave number")?)?;
+                if wave < 1 {
+                    return Err("waves start at 1".to_string());
+                }
+                if state.tutorial.is_some() {
+                    return Err("the tutorial doesn't have waves".to_string());
+                }
+                // tick_spawner starts it on the next tick. waves count from 0, but the hud from 1
+                state.next_wave_num = wave - 1;
+                state.next_wave_at_tick = state.tick;
+                vec![format!("wave {} starts when the console's closed", wave)]
+            }
+            "god" => {
+                state.god = !state.god;
+                let on_off = if state.god { "on" } else { "off" };
+                vec![format!("god mode {}", on_off)]
+            }
+            "timescale" => {
+                let scale: f32 = number(arg("a scale, like 0.5")?)?;
+                if !(scale > 0.0 && scale <= TIME_SCALE_MAX) {
+                    return Err(format!(
+                        "the time scale needs to be more than 0, and at most {}",
+                        TIME_SCALE_MAX
+                    ));
+                }
+                self.time_scale = scale;
+                vec![format!("running at {}x", scale)]
+            }
+            "seed" => {
+                let seed: u64 = number(arg("a seed")?)?;
+                macroquad::rand::srand(seed);
+                *state = state.restart();
+                vec![format!("seeded with {} and restarted", seed)]
+            }
+            "dump" => {
+                // stderr can't be seen on the web, so it's said here too. swarm games can have more
+                // than LINES_KEPT lines, and only the end of those is kept
+                let dump = dump(state);
+                eprint!("{}", dump);
+                let mut said: Vec<String> = dump.lines().map(str::to_string).collect();
+                said.push("printed to stderr too".to_string());
+                said
+            }
+            _ => return Err(format!("there's no command called '{}'", name)),
+        };
+        Ok(said)
+    }
+
+    // drawn over everything, including the hud
+    pub fn draw(&self, font: Font) {
+        if !self.open {
+            return;
+        }
+
+        macroquad::camera::set_camera(&camera::hud_camera());
+        draw_rectangle(
+            0.0,
+            0.0,
+            VIEW_WIDTH,
+            HEIGHT,
+            Color::new(0.0, 0.0, 0.0, 0.85),
+        );
+        let mut layout = Layout::new(font, Anchor::TopLeft);
+        let end = self.lines.len() - self.scrolled;
+        let shown = end.saturating_sub(LINES_SHOWN);
+        layout.gap((LINES_SHOWN - (end - shown)) as f32 * (TEXT_SIZE + hud::SPACING));
+        for (line, colour) in &self.lines[shown..end] {
+            layout.text(line, TEXT_SIZE, *colour);
+        }
+        // a blinking cursor
+        let cursor = if get_time() % 1.0 < 0.5 { "_" } else { "" };
+        layout.text(&format!("> {}{}", self.input, cursor), TEXT_SIZE, WHITE);
+    }
+}
+
+fn number<T: std::str::FromStr>(word: &str) -> Result<T, String> {
+    word.parse()
+        .map_err(|_| format!("'{}' isn't a number", word))
+}
+
+// everything about the game in progress, apart from the level, waves and tuning, which come from
+// files anyway
+fn dump(state: &GameState) -> String {
+    let xy = |v: Vec2| format!("({:.1}, {:.1})", v.x, v.y);
+    let player_state = match state.player_state() {
+        PlayerState::Walk => "walking",
+        PlayerState::Roll => "rolling",
+        PlayerState::Recover => "recovering",
+        PlayerState::Dead => "dead",
+    };
+
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "tick {}, score {}, wave {} next at tick {}, god mode {}",
+        state.tick, state.score, state.next_wave_num, state.next_wave_at_tick, state.god
+    );
+    if let Some(t) = &state.tutorial {
+        let _ = writeln!(out, "tutorial step {}/{}", t.step_num(), t.step_count());
+    }
+    let _ = writeln!(
+        out,
+        "player at {} going {}, {}, rolling until tick {}",
+        xy(state.player_pos),
+        xy(state.player_dir),
+        player_state,
+        state.player_rolling_until
+    );
+    let _ = writeln!(
+        out,
+        "knife at {} pointing {}",
+        xy(state.knife_pos),
+        xy(state.knife_dir)
+    );
+    for (i, l) in state.lemons.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "lemon {} at {}, wandering to {}, attacks in {}",
+            i,
+            xy(l.pos),
+            xy(l.wander_to),
+            l.attacks_in
+        );
+    }
+    for (i, g) in state.grapes.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "grape {} at {}, attacks in {}, {} shots left, next in {}",
+            i,
+            xy(g.pos),
+            g.attacks_in,
+            g.shots_left,
+            g.next_shot_in
+        );
+    }
+    for (i, b) in state.breads.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "bread {} at {}, charges in {}, at {}",
+            i,
+            xy(b.pos),
+            b.ticks_until_charge,
+            xy(b.attacking)
+        );
+    }
+    for (i, b) in state.bullets.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "bullet {} at {} going {}, homes in {}",
+            i,
+            xy(b.pos),
+            xy(b.dir),
+            b.home_in
+        );
+    }
+    let _ = writeln!(out, "{} corpses", state.corpses.len());
+    out
+}
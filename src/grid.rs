@@ -0,0 +1,57 @@
+// uniform grid broadphase for collision checks. things are bucketed by the cell their centre falls
+// in, so queries need to pad their radius by the biggest radius of whatever's stored.
+use macroquad::prelude::*;
+
+pub struct Grid {
+    cell_size: f32,
+    cols: usize,
+    rows: usize,
+    cells: Vec<Vec<u32>>,
+}
+
+impl Grid {
+    pub fn new(width: f32, height: f32, cell_size: f32) -> Self {
+        let cols = (width / cell_size).ceil().max(1.0) as usize;
+        let rows = (height / cell_size).ceil().max(1.0) as usize;
+        Self {
+            cell_size,
+            cols,
+            rows,
+            cells: vec![Vec::new(); cols * rows],
+        }
+    }
+
+    // empties the grid and fills it with `positions`, using each one's index as its id. keeps the
+    // allocations around, so rebuilding every tick is cheap.
+    pub fn build(&mut self, positions: impl Iterator<Item = Vec2>) {
+        for c in &mut self.cells {
+            c.clear();
+        }
+
+        for (id, pos) in positions.enumerate() {
+            let (cx, cy) = self.cell_of(pos);
+            self.cells[cy * self.cols + cx].push(id as u32);
+        }
+    }
+
+    // calls `f` with the id of everything in a cell that a circle at `pos` with `radius` touches.
+    // this is a superset of what actually overlaps, callers still need to check distances.
+    pub fn query(&self, pos: Vec2, radius: f32, mut f: impl FnMut(usize)) {
+        let (x0, y0) = self.cell_of(pos - vec2(radius, radius));
+        let (x1, y1) = self.cell_of(pos + vec2(radius, radius));
+        for cy in y0..=y1 {
+            for cx in x0..=x1 {
+                for id in &self.cells[cy * self.cols + cx] {
+                    f(*id as usize);
+                }
+            }
+        }
+    }
+
+    // anything outside the grid is put in the nearest edge cell
+    fn cell_of(&self, pos: Vec2) -> (usize, usize) {
+        let cx = (pos.x / self.cell_size).max(0.0) as usize;
+        let cy = (pos.y / self.cell_size).max(0.0) as usize;
+        (cx.min(self.cols - 1), cy.min(self.rows - 1))
+    }
+}
@@ -0,0 +1,121 @@
+// arena hazards. stoves flare up every so often, burning anything standing on them, and conveyor
+// belts push everything on them along.
+use crate::TICKS_PER_SEC;
+use macroquad::prelude::*;
+
+// how long a stove glows before flaring up, and how long the flare lasts
+const STOVE_WARN_TICKS: i32 = TICKS_PER_SEC * 3 / 2;
+const STOVE_FLARE_TICKS: i32 = TICKS_PER_SEC * 3 / 4;
+pub const STOVE_DEFAULT_PERIOD: f32 = 5.0;
+// conveyor stripes, for showing which way they go
+const STRIPE_GAP: f32 = 24.0;
+
+#[derive(Clone)]
+pub enum Hazard {
+    // flares every `period` ticks, the first time after `delay` ticks
+    Stove { rect: Rect, period: i32, delay: i32 },
+    // moves anything on it by `push` every tick
+    Conveyor { rect: Rect, push: Vec2 },
+}
+
+impl Hazard {
+    pub fn stove(rect: Rect, period_secs: f32, delay_secs: f32) -> Self {
+        let min_period = STOVE_WARN_TICKS + STOVE_FLARE_TICKS + 1;
+        Hazard::Stove {
+            rect,
+            period: ((period_secs * TICKS_PER_SEC as f32) as i32).max(min_period),
+            delay: (delay_secs * TICKS_PER_SEC as f32) as i32,
+        }
+    }
+
+    // how far through the warning a stove is, 0 to 1, and whether it's flaring.
+    fn stove_phase(period: i32, delay: i32, tick: i32) -> (f32, bool) {
+        let t = (tick - delay).rem_euclid(period);
+        let flare_at = period - STOVE_FLARE_TICKS;
+        let warn_at = flare_at - STOVE_WARN_TICKS;
+        if tick < delay || t < warn_at {
+            (0.0, false)
+        } else if t < flare_at {
+            ((t - warn_at) as f32 / STOVE_WARN_TICKS as f32, false)
+        } else {
+            (1.0, true)
+        }
+    }
+
+    pub fn burns(&self, pos: Vec2, tick: i32) -> bool {
+        match *self {
+            Hazard::Stove {
+                rect,
+                period,
+                delay,
+            } => rect.contains(pos) && Self::stove_phase(period, delay, tick).1,
+            Hazard::Conveyor { .. } => false,
+        }
+    }
+
+    pub fn push(&self, pos: Vec2) -> Vec2 {
+        match *self {
+            Hazard::Conveyor { rect, push } if rect.contains(pos) => push,
+            _ => Vec2::ZERO,
+        }
+    }
+
+    pub fn render(&self, tick: i32) {
+        match *self {
+            Hazard::Stove {
+                rect,
+                period,
+                delay,
+            } => {
+                draw_rectangle(
+                    rect.x,
+                    rect.y,
+                    rect.w,
+                    rect.h,
+                    Color::new(0.15, 0.15, 0.17, 1.0),
+                );
+
+                let (warn, flaring) = Self::stove_phase(period, delay, tick);
+                let glow = if flaring {
+                    // flicker while it's actually dangerous
+                    Color::new(1.0, 0.45 + (tick as f32 * 0.8).sin() * 0.15, 0.05, 0.9)
+                } else {
+                    Color::new(1.0, 0.3, 0.0, warn * warn * 0.6)
+                };
+                draw_rectangle(rect.x, rect.y, rect.w, rect.h, glow);
+
+                // a burner ring in the middle
+                let c = vec2(rect.x + rect.w / 2.0, rect.y + rect.h / 2.0);
+                let r = rect.w.min(rect.h) * 0.35;
+                draw_circle_lines(c.x, c.y, r, 3.0, Color::new(0.4, 0.4, 0.45, 1.0));
+            }
+            Hazard::Conveyor { rect, push } => {
+                draw_rectangle(
+                    rect.x,
+                    rect.y,
+                    rect.w,
+                    rect.h,
+                    Color::new(0.2, 0.2, 0.2, 1.0),
+                );
+
+                // stripes across the belt, scrolling the way it pushes
+                let stripe_col = Color::new(0.45, 0.45, 0.4, 1.0);
+                let horizontal = push.x.abs() >= push.y.abs();
+                let speed = if horizontal { push.x } else { push.y };
+                let len = if horizontal { rect.w } else { rect.h };
+                let mut at = (tick as f32 * speed).rem_euclid(STRIPE_GAP);
+                while at < len {
+                    if horizontal {
+                        let x = rect.x + at;
+                        draw_line(x, rect.y, x, rect.bottom(), 3.0, stripe_col);
+                    } else {
+                        let y = rect.y + at;
+                        draw_line(rect.x, y, rect.right(), y, 3.0, stripe_col);
+                    }
+                    at += STRIPE_GAP;
+                }
+                draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, 3.0, BLACK);
+            }
+        }
+    }
+}
@@ -0,0 +1,143 @@
+// heads up display. widgets are stacked up from anchors at the corners and middle of the view, so
+// they stay put whatever shape the window is. sizes are in view pixels, like the rest of the hud,
+// but text is rasterised at the size it actually ends up on screen so it stays sharp.
+use crate::{camera, GameState, PlayerState, TICKS_PER_SEC, VIEW_HEIGHT, VIEW_WIDTH};
+use macroquad::prelude::*;
+
+// gap between widgets and the edge of the view, and between one widget and the next
+const MARGIN: f32 = 20.0;
+pub const SPACING: f32 = 6.0;
+const BAR_OUTLINE: f32 = 2.0;
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum Anchor {
+    TopLeft,
+    TopRight,
+    Centre,
+    BottomLeft,
+    BottomRight,
+}
+
+impl Anchor {
+    fn is_bottom(self) -> bool {
+        matches!(self, Anchor::BottomLeft | Anchor::BottomRight)
+    }
+}
+
+// a stack of widgets going away from an anchor. downwards, apart from stacks at the bottom of the
+// view, which go upwards. stacks from the centre start in the middle of the view.
+pub struct Layout {
+    font: Font,
+    anchor: Anchor,
+    // how far along the stack the next widget goes
+    used: f32,
+}
+
+impl Layout {
+    pub fn new(font: Font, anchor: Anchor) -> Self {
+        Self {
+            font,
+            anchor,
+            used: 0.0,
+        }
+    }
+
+    // extra space before the next widget
+    pub fn gap(&mut self, px: f32) {
+        self.used += px;
+    }
+
+    // a line of text, `size` pixels tall
+    pub fn text(&mut self, text: &str, size: f32, colour: Color) {
+        let scale = camera::view_scale();
+        let font_size = (size * scale).round().max(1.0) as u16;
+        let dims = measure_text(text, Some(self.font), font_size, 1.0 / scale);
+
+        let at = self.place(vec2(dims.width, size));
+        draw_text_ex(
+            text,
+            at.x,
+            // baseline, leaving room for descenders
+            at.y + size * 0.8,
+            TextParams {
+                font: self.font,
+                font_size,
+                font_scale: 1.0 / scale,
+                color: colour,
+                ..Default::default()
+            },
+        );
+    }
+
+    // a bar filled `fill` of the way, from 0 to 1
+    pub fn bar(&mut self, size: Vec2, fill: f32, colour: Color) {
+        let at = self.place(size);
+        draw_rectangle(at.x, at.y, size.x, size.y, Color::new(0.0, 0.0, 0.0, 0.5));
+        draw_rectangle(at.x, at.y, size.x * fill.clamp(0.0, 1.0), size.y, colour);
+        draw_rectangle_lines(at.x, at.y, size.x, size.y, BAR_OUTLINE, WHITE);
+    }
+
+    // top left corner for the next widget, and moves on past it
+    fn place(&mut self, size: Vec2) -> Vec2 {
+        let x = match self.anchor {
+            Anchor::TopLeft | Anchor::BottomLeft => MARGIN,
+            Anchor::Centre => (VIEW_WIDTH - size.x) / 2.0,
+            Anchor::TopRight | Anchor::BottomRight => VIEW_WIDTH - MARGIN - size.x,
+        };
+        let y = if self.anchor.is_bottom() {
+            VIEW_HEIGHT - MARGIN - self.used - size.y
+        } else if self.anchor == Anchor::Centre {
+            VIEW_HEIGHT / 2.0 + self.used
+        } else {
+            MARGIN + self.used
+        };
+        self.used += size.y + SPACING;
+        vec2(x, y)
+    }
+}
+
+// the hud for a game in progress. needs the hud camera to be active
+pub fn draw(state: &GameState, font: Font) {
+    let secs = state.tick / TICKS_PER_SEC;
+    let mut help = Layout::new(font, Anchor::TopLeft);
+    if let Some(t) = &state.tutorial {
+        let step = format!("Tutorial {}/{}", t.step_num(), t.step_count());
+        help.text(&step, 20.0, LIGHTGRAY);
+        for line in t.text() {
+            help.text(line, 26.0, WHITE);
+        }
+        if let Some(feedback) = t.feedback(state.tick) {
+            help.gap(SPACING);
+            help.text(feedback, 30.0, GOLD);
+        }
+    } else {
+        help.text("WASD to move, space to roll", 20.0, WHITE);
+
+        let mut progress = Layout::new(font, Anchor::TopRight);
+        progress.text(&format!("{}:{:02}", secs / 60, secs % 60), 36.0, WHITE);
+        progress.text(&format!("Score {}", state.score), 24.0, WHITE);
+        progress.text(&format!("Wave {}", state.next_wave_num), 20.0, LIGHTGRAY);
+    }
+
+    let mut roll = Layout::new(font, Anchor::BottomLeft);
+    let ready = state.roll_ready();
+    let roll_colour = if ready >= 1.0 { SKYBLUE } else { GRAY };
+    roll.bar(vec2(160.0, 14.0), ready, roll_colour);
+    roll.text("Roll", 20.0, WHITE);
+
+    let enemies = state.lemons.len() + state.grapes.len() + state.breads.len();
+    let mut remaining = Layout::new(font, Anchor::BottomRight);
+    remaining.text(&format!("{} left", enemies), 24.0, WHITE);
+
+    if state.player_state() == PlayerState::Dead {
+        let mut over = Layout::new(font, Anchor::Centre);
+        over.gap(-60.0);
+        over.text("Game over", 48.0, WHITE);
+        over.text(
+            &format!("You lasted {} seconds, and scored {}", secs, state.score),
+            26.0,
+            WHITE,
+        );
+        over.text("Press R to restart, or T for the tutorial", 22.0, LIGHTGRAY);
+    }
+}
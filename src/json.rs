@@ -0,0 +1,313 @@
+// just enough of a json parser to read maps exported from Tiled.
+
+// arrays and objects inside each other any deeper than this are an error rather than a stack
+// overflow. Tiled maps only go a few deep
+const MAX_DEPTH: usize = 64;
+
+pub enum Value {
+    Null,
+    Bool(bool),
+    Num(f64),
+    Str(String),
+    Arr(Vec<Value>),
+    Obj(Vec<(String, Value)>),
+}
+
+impl Value {
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Obj(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_f32(&self) -> Option<f32> {
+        match self {
+            Value::Num(n) => Some(*n as f32),
+            _ => None,
+        }
+    }
+
+    // whole numbers that fit in a u32, like Tiled's tile ids. as_f32 would lose their top bits
+    pub fn as_u32(&self) -> Option<u32> {
+        match self {
+            Value::Num(n) if n.fract() == 0.0 && (0.0..=u32::MAX as f64).contains(n) => {
+                Some(*n as u32)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_arr(&self) -> Option<&[Value]> {
+        match self {
+            Value::Arr(a) => Some(a),
+            _ => None,
+        }
+    }
+}
+
+pub fn parse(text: &str) -> Result<Value, String> {
+    let mut p = Parser {
+        bytes: text.as_bytes(),
+        at: 0,
+        depth: 0,
+    };
+    let v = p.value()?;
+    p.skip_space();
+    if p.at != p.bytes.len() {
+        return Err(p.error("trailing characters"));
+    }
+    Ok(v)
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    at: usize,
+    // how many arrays and objects the parser's inside
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, what: &str) -> String {
+        let line = self.bytes[..self.at]
+            .iter()
+            .filter(|b| **b == b'\n')
+            .count()
+            + 1;
+        format!("json line {}: {}", line, what)
+    }
+
+    fn skip_space(&mut self) {
+        while self.at < self.bytes.len() && self.bytes[self.at].is_ascii_whitespace() {
+            self.at += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.at).copied()
+    }
+
+    fn expect(&mut self, c: u8) -> Result<(), String> {
+        self.skip_space();
+        if self.peek() != Some(c) {
+            return Err(self.error(&format!("expected '{}'", c as char)));
+        }
+        self.at += 1;
+        Ok(())
+    }
+
+    fn literal(&mut self, word: &str, v: Value) -> Result<Value, String> {
+        if self.bytes[self.at..].starts_with(word.as_bytes()) {
+            self.at += word.len();
+            Ok(v)
+        } else {
+            Err(self.error("unexpected character"))
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.skip_space();
+        match self.peek() {
+            Some(b'{') => self.nested(Self::object),
+            Some(b'[') => self.nested(Self::array),
+            Some(b'"') => Ok(Value::Str(self.string()?)),
+            Some(b't') => self.literal("true", Value::Bool(true)),
+            Some(b'f') => self.literal("false", Value::Bool(false)),
+            Some(b'n') => self.literal("null", Value::Null),
+            Some(c) if c == b'-' || c.is_ascii_digit() => self.number(),
+            Some(_) => Err(self.error("unexpected character")),
+            None => Err(self.error("unexpected end of file")),
+        }
+    }
+
+    fn nested(&mut self, parse: fn(&mut Self) -> Result<Value, String>) -> Result<Value, String> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error("nested too deeply"));
+        }
+        self.depth += 1;
+        let v = parse(self);
+        self.depth -= 1;
+        v
+    }
+
+    fn object(&mut self) -> Result<Value, String> {
+        self.expect(b'{')?;
+        let mut fields = Vec::new();
+        self.skip_space();
+        if self.peek() == Some(b'}') {
+            self.at += 1;
+            return Ok(Value::Obj(fields));
+        }
+
+        loop {
+            self.skip_space();
+            let key = self.string()?;
+            self.expect(b':')?;
+            fields.push((key, self.value()?));
+
+            self.skip_space();
+            match self.peek() {
+                Some(b',') => self.at += 1,
+                Some(b'}') => {
+                    self.at += 1;
+                    return Ok(Value::Obj(fields));
+                }
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, String> {
+        self.expect(b'[')?;
+        let mut items = Vec::new();
+        self.skip_space();
+        if self.peek() == Some(b']') {
+            self.at += 1;
+            return Ok(Value::Arr(items));
+        }
+
+        loop {
+            items.push(self.value()?);
+
+            self.skip_space();
+            match self.peek() {
+                Some(b',') => self.at += 1,
+                Some(b']') => {
+                    self.at += 1;
+                    return Ok(Value::Arr(items));
+                }
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        if self.peek() != Some(b'"') {
+            return Err(self.error("expected a string"));
+        }
+        self.at += 1;
+
+        let mut out = String::new();
+        loop {
+            let start = self.at;
+            while self.at < self.bytes.len() && !matches!(self.bytes[self.at], b'"' | b'\\') {
+                self.at += 1;
+            }
+            // only ever split at ascii characters, so this is still valid utf8
+            out.push_str(std::str::from_utf8(&self.bytes[start..self.at]).unwrap());
+
+            match self.peek() {
+                Some(b'"') => {
+                    self.at += 1;
+                    return Ok(out);
+                }
+                Some(b'\\') => {
+                    self.at += 1;
+                    let c = match self.peek() {
+                        Some(b'"') => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'/') => '/',
+                        Some(b'n') => '\n',
+                        Some(b't') => '\t',
+                        Some(b'r') => '\r',
+                        Some(b'b') => '\u{8}',
+                        Some(b'f') => '\u{c}',
+                        Some(b'u') => {
+                            let hex = self
+                                .bytes
+                                .get(self.at + 1..self.at + 5)
+                                .and_then(|h| std::str::from_utf8(h).ok())
+                                .and_then(|h| u32::from_str_radix(h, 16).ok())
+                                .ok_or_else(|| self.error("bad \\u escape"))?;
+                            self.at += 4;
+                            // surrogate pairs aren't handled, Tiled won't write them for anything
+                            // we care about
+                            char::from_u32(hex).unwrap_or(char::REPLACEMENT_CHARACTER)
+                        }
+                        _ => return Err(self.error("bad escape")),
+                    };
+                    out.push(c);
+                    self.at += 1;
+                }
+                _ => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, String> {
+        let start = self.at;
+        while self.at < self.bytes.len()
+            && matches!(
+                self.bytes[self.at],
+                b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9'
+            )
+        {
+            self.at += 1;
+        }
+        std::str::from_utf8(&self.bytes[start..self.at])
+            .ok()
+            .and_then(|n| n.parse().ok())
+            .map(Value::Num)
+            .ok_or_else(|| self.error("bad number"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_a_map() {
+        let v =
+            parse(r#"{ "width": 3, "infinite": false, "layers": [{ "name": "floor" }, null] }"#)
+                .unwrap();
+        assert_eq!(v.get("width").and_then(Value::as_f32), Some(3.0));
+        assert_eq!(v.get("infinite").and_then(Value::as_bool), Some(false));
+        assert_eq!(v.get("width").and_then(Value::as_u32), Some(3));
+        let layers = v.get("layers").and_then(Value::as_arr).unwrap();
+        assert_eq!(layers.len(), 2);
+        assert_eq!(layers[0].get("name").and_then(Value::as_str), Some("floor"));
+        assert!(matches!(layers[1], Value::Null));
+    }
+
+    #[test]
+    fn parses_escapes() {
+        let v = parse(r#""a\"b\\c\n\u00e9""#).unwrap();
+        assert_eq!(v.as_str(), Some("a\"b\\c\n\u{e9}"));
+    }
+
+    #[test]
+    fn rejects_bad_json() {
+        assert!(parse("").is_err());
+        assert!(parse("[1, 2").is_err());
+        assert!(parse(r#"{ "a" 1 }"#).is_err());
+        assert!(parse(r#""unterminated"#).is_err());
+        assert!(parse("[1] 2").is_err());
+        assert!(parse("tru").is_err());
+    }
+
+    #[test]
+    fn rejects_deep_nesting() {
+        let ok = "[".repeat(MAX_DEPTH) + &"]".repeat(MAX_DEPTH);
+        assert!(parse(&ok).is_ok());
+        let deep = "[".repeat(100_000) + &"]".repeat(100_000);
+        match parse(&deep) {
+            Err(e) => assert!(e.contains("nested too deeply"), "{}", e),
+            Ok(_) => panic!("parsed json nested 100000 deep"),
+        }
+    }
+}
@@ -0,0 +1,450 @@
+// arena layouts. a level is the size of the arena and a list of static obstacles that block the
+// player, enemies and bullets, read from a text file with one thing per line:
+//
+//   size <w> <h>
+//   counter <x> <y> <w> <h>
+//   table <x> <y> <w> <h>
+//   pot <x> <y> <radius>
+//   stove <x> <y> <w> <h> [<seconds between flares> [<seconds before first flare>]]
+//   conveyor <x> <y> <w> <h> <push x> <push y>
+//
+// anything after a # is a comment. arenas without a size are the size of the screen.
+//
+// levels can also be made in Tiled, see tiled.rs.
+use crate::assets::Assets;
+use crate::hazards::{Hazard, STOVE_DEFAULT_PERIOD};
+use crate::tiled;
+use crate::{VIEW_HEIGHT, VIEW_WIDTH};
+use macroquad::prelude::*;
+
+#[derive(Copy, Clone)]
+pub enum Kind {
+    Counter,
+    Table,
+    Pot,
+}
+
+#[derive(Copy, Clone)]
+pub enum Shape {
+    Rect(Rect),
+    Circle(Vec2, f32),
+}
+
+#[derive(Clone)]
+pub struct Obstacle {
+    pub kind: Kind,
+    pub shape: Shape,
+}
+
+#[derive(Clone)]
+pub struct Level {
+    pub size: Vec2,
+    pub obstacles: Vec<Obstacle>,
+    pub hazards: Vec<Hazard>,
+    // floor tiles, for levels from Tiled. other levels get the plain background
+    pub tiles: Option<Tiles>,
+    pub player_start: Option<Vec2>,
+    // if there's any, enemies spawn around these rather than anywhere
+    pub spawn_points: Vec<Vec2>,
+}
+
+#[derive(Clone)]
+pub struct Tiles {
+    pub tile_size: Vec2,
+    // width of the map, in tiles
+    pub columns: usize,
+    // tile ids, row by row. 0 is empty
+    pub layers: Vec<Vec<u32>>,
+    // sorted by first_gid
+    pub tilesets: Vec<Tileset>,
+}
+
+#[derive(Clone)]
+pub struct Tileset {
+    pub first_gid: u32,
+    // path of the image, relative to the map
+    pub image: String,
+    pub texture: Option<Texture2D>,
+    pub columns: u32,
+    pub tile_size: Vec2,
+    pub margin: f32,
+    pub spacing: f32,
+}
+
+const DEFAULT: &str = include_str!("levels/kitchen.json");
+
+// loads the level at `path`, or the default kitchen if there isn't one. paths ending in .json are
+// read as Tiled maps, anything else as a level file.
+pub async fn load(path: Option<&str>, ass: &Assets) -> Result<Level, String> {
+    let path = match path {
+        Some(path) => path,
+        None => {
+            // the default's tileset is the normal background, which is already loaded
+            let mut level = tiled::parse(DEFAULT)?;
+            for ts in level.tiles.iter_mut().flat_map(|t| t.tilesets.iter_mut()) {
+                ts.texture = Some(ass.background);
+            }
+            return Ok(level);
+        }
+    };
+
+    let text = macroquad::file::load_string(path)
+        .await
+        .map_err(|e| e.to_string())?;
+    if !path.ends_with(".json") {
+        return parse(&text).map_err(|e| format!("{}: {}", path, e));
+    }
+
+    let mut level = tiled::parse(&text).map_err(|e| format!("{}: {}", path, e))?;
+    let dir = std::path::Path::new(path)
+        .parent()
+        .unwrap_or(std::path::Path::new(""));
+    for ts in level.tiles.iter_mut().flat_map(|t| t.tilesets.iter_mut()) {
+        let image = dir.join(&ts.image);
+        let image = image.to_string_lossy();
+        let tex = load_texture(&image).await.map_err(|e| e.to_string())?;
+        ts.texture = Some(tex);
+    }
+    Ok(level)
+}
+
+pub fn parse(text: &str) -> Result<Level, String> {
+    let mut size = vec2(VIEW_WIDTH, VIEW_HEIGHT);
+    let mut obstacles = Vec::new();
+    let mut hazards = Vec::new();
+
+    for (line_num, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("");
+        let mut words = line.split_whitespace();
+        let kind = match words.next() {
+            None => continue,
+            Some(k) => k,
+        };
+
+        let nums: Result<Vec<f32>, _> = words.map(|w| w.parse::<f32>()).collect();
+        let nums = nums.map_err(|e| format!("line {}: {}", line_num + 1, e))?;
+
+        let obstacle = match (kind, nums.as_slice()) {
+            ("size", &[w, h]) => {
+                size = vec2(w, h);
+                if !valid_size(size) {
+                    return Err(format!(
+                        "line {}: the size needs to be more than 0 each way, not {} {}",
+                        line_num + 1,
+                        w,
+                        h
+                    ));
+                }
+                continue;
+            }
+            ("stove", &[x, y, w, h, ref timing @ ..]) if timing.len() <= 2 => {
+                let period = timing.first().copied().unwrap_or(STOVE_DEFAULT_PERIOD);
+                let delay = timing.get(1).copied().unwrap_or(0.0);
+                hazards.push(Hazard::stove(Rect::new(x, y, w, h), period, delay));
+                continue;
+            }
+            ("conveyor", &[x, y, w, h, dx, dy]) => {
+                hazards.push(Hazard::Conveyor {
+                    rect: Rect::new(x, y, w, h),
+                    push: vec2(dx, dy),
+                });
+                continue;
+            }
+            ("counter", &[x, y, w, h]) => Obstacle {
+                kind: Kind::Counter,
+                shape: Shape::Rect(Rect::new(x, y, w, h)),
+            },
+            ("table", &[x, y, w, h]) => Obstacle {
+                kind: Kind::Table,
+                shape: Shape::Rect(Rect::new(x, y, w, h)),
+            },
+            ("pot", &[x, y, r]) => Obstacle {
+                kind: Kind::Pot,
+                shape: Shape::Circle(vec2(x, y), r),
+            },
+            _ => {
+                return Err(format!(
+                    "line {}: can't understand '{}'",
+                    line_num + 1,
+                    line.trim()
+                ))
+            }
+        };
+        obstacles.push(obstacle);
+    }
+
+    Ok(Level {
+        size,
+        obstacles,
+        hazards,
+        tiles: None,
+        player_start: None,
+        spawn_points: Vec::new(),
+    })
+}
+
+// whether a level can be this size. keep_in_bounds panics clamping to anything else
+pub fn valid_size(size: Vec2) -> bool {
+    size.x.is_finite() && size.y.is_finite() && size.x > 0.0 && size.y > 0.0
+}
+
+impl Level {
+    pub fn centre(&self) -> Vec2 {
+        self.size / 2.0
+    }
+
+    pub fn in_bounds(&self, pos: Vec2) -> bool {
+        pos.x >= 0.0 && pos.y >= 0.0 && pos.x <= self.size.x && pos.y <= self.size.y
+    }
+
+    pub fn keep_in_bounds(&self, pos: &mut Vec2) {
+        pos.x = pos.x.clamp(0.0, self.size.x);
+        pos.y = pos.y.clamp(0.0, self.size.y);
+    }
+
+    // whether a circle at `pos` overlaps any obstacle
+    pub fn overlaps(&self, pos: Vec2, radius: f32) -> bool {
+        self.obstacles
+            .iter()
+            .any(|o| o.shape.push_out(pos, radius).is_some())
+    }
+
+    // whether a stove is flaring under `pos`
+    pub fn burns(&self, pos: Vec2, tick: i32) -> bool {
+        self.hazards.iter().any(|h| h.burns(pos, tick))
+    }
+
+    // how far conveyor belts move anything at `pos` this tick
+    pub fn conveyor_push(&self, pos: Vec2) -> Vec2 {
+        self.hazards
+            .iter()
+            .fold(Vec2::ZERO, |push, h| push + h.push(pos))
+    }
+
+    // moves a circle at `pos` out of any obstacles it overlaps. returns whether it had to move.
+    pub fn push_out(&self, pos: &mut Vec2, radius: f32) -> bool {
+        let mut moved = false;
+        for o in &self.obstacles {
+            if let Some(out) = o.shape.push_out(*pos, radius) {
+                *pos = out;
+                moved = true;
+            }
+        }
+        moved
+    }
+
+    // obstacles within `range` of `pos`, as circles for steering around. rects give the closest
+    // point on their edge.
+    pub fn nearby_circles(&self, pos: Vec2, range: f32, out: &mut Vec<(Vec2, f32)>) {
+        for o in &self.obstacles {
+            let circle = match o.shape {
+                Shape::Rect(r) => (closest_point(r, pos), 0.0),
+                Shape::Circle(c, r) => (c, r),
+            };
+            if circle.0.distance(pos) - circle.1 < range {
+                out.push(circle);
+            }
+        }
+    }
+}
+
+impl Shape {
+    // where a circle at `pos` needs to move to to stop overlapping this shape, if it overlaps.
+    fn push_out(&self, pos: Vec2, radius: f32) -> Option<Vec2> {
+        match *self {
+            Shape::Circle(centre, r) => {
+                let off = pos - centre;
+                let min_dist = r + radius;
+                if off.length_squared() >= min_dist * min_dist {
+                    return None;
+                }
+                let dir = if off == Vec2::ZERO {
+                    vec2(1.0, 0.0)
+                } else {
+                    off.normalize()
+                };
+                Some(centre + dir * min_dist)
+            }
+            Shape::Rect(rect) => {
+                let closest = closest_point(rect, pos);
+                let off = pos - closest;
+                if off.length_squared() >= radius * radius {
+                    return None;
+                }
+                if off != Vec2::ZERO {
+                    return Some(closest + off.normalize() * radius);
+                }
+
+                // centre is inside the rect, go out the nearest side
+                let to_left = pos.x - rect.x;
+                let to_right = rect.right() - pos.x;
+                let to_top = pos.y - rect.y;
+                let to_bottom = rect.bottom() - pos.y;
+                let nearest = to_left.min(to_right).min(to_top).min(to_bottom);
+                Some(if nearest == to_left {
+                    vec2(rect.x - radius, pos.y)
+                } else if nearest == to_right {
+                    vec2(rect.right() + radius, pos.y)
+                } else if nearest == to_top {
+                    vec2(pos.x, rect.y - radius)
+                } else {
+                    vec2(pos.x, rect.bottom() + radius)
+                })
+            }
+        }
+    }
+}
+
+fn closest_point(rect: Rect, pos: Vec2) -> Vec2 {
+    vec2(
+        pos.x.clamp(rect.x, rect.right()),
+        pos.y.clamp(rect.y, rect.bottom()),
+    )
+}
+
+// draws the floor, hazards and obstacles. only tiles inside `view` are drawn.
+pub fn render(level: &Level, view: Rect, ass: &Assets, tick: i32) {
+    match &level.tiles {
+        Some(tiles) => render_tiles(tiles, view),
+        None => {
+            // tile the background over arenas bigger than it
+            let mut y = 0.0;
+            while y < level.size.y {
+                let mut x = 0.0;
+                while x < level.size.x {
+                    draw_texture(ass.background, x, y, WHITE);
+                    x += ass.background.width();
+                }
+                y += ass.background.height();
+            }
+        }
+    }
+
+    for h in &level.hazards {
+        h.render(tick);
+    }
+
+    for o in &level.obstacles {
+        let (fill, edge) = match o.kind {
+            Kind::Counter => (
+                Color::new(0.55, 0.56, 0.6, 1.0),
+                Color::new(0.3, 0.3, 0.35, 1.0),
+            ),
+            Kind::Table => (
+                Color::new(0.55, 0.36, 0.2, 1.0),
+                Color::new(0.35, 0.2, 0.1, 1.0),
+            ),
+            Kind::Pot => (
+                Color::new(0.25, 0.25, 0.28, 1.0),
+                Color::new(0.6, 0.6, 0.65, 1.0),
+            ),
+        };
+        match o.shape {
+            Shape::Rect(r) => {
+                draw_rectangle(r.x, r.y, r.w, r.h, fill);
+                draw_rectangle_lines(r.x, r.y, r.w, r.h, 4.0, edge);
+            }
+            Shape::Circle(c, r) => {
+                draw_circle(c.x, c.y, r, fill);
+                draw_circle_lines(c.x, c.y, r, 4.0, edge);
+            }
+        }
+    }
+}
+
+fn render_tiles(tiles: &Tiles, view: Rect) {
+    let rows = tiles.layers.first().map_or(0, |l| l.len()) / tiles.columns.max(1);
+    let first_col = (view.x / tiles.tile_size.x).floor().max(0.0) as usize;
+    let first_row = (view.y / tiles.tile_size.y).floor().max(0.0) as usize;
+    let last_col = ((view.right() / tiles.tile_size.x).ceil() as usize).min(tiles.columns);
+    let last_row = ((view.bottom() / tiles.tile_size.y).ceil() as usize).min(rows);
+
+    for layer in &tiles.layers {
+        for row in first_row..last_row {
+            for col in first_col..last_col {
+                let gid = layer.get(row * tiles.columns + col).copied().unwrap_or(0);
+                if gid == 0 {
+                    continue;
+                }
+                let ts = match tiles.tilesets.iter().rev().find(|ts| ts.first_gid <= gid) {
+                    Some(ts) => ts,
+                    None => continue,
+                };
+                let tex = match ts.texture {
+                    Some(tex) => tex,
+                    None => continue,
+                };
+
+                let local = gid - ts.first_gid;
+                let src = Rect::new(
+                    ts.margin + (local % ts.columns.max(1)) as f32 * (ts.tile_size.x + ts.spacing),
+                    ts.margin + (local / ts.columns.max(1)) as f32 * (ts.tile_size.y + ts.spacing),
+                    ts.tile_size.x,
+                    ts.tile_size.y,
+                );
+                let params = DrawTextureParams {
+                    dest_size: Some(tiles.tile_size),
+                    source: Some(src),
+                    ..Default::default()
+                };
+                draw_texture_ex(
+                    tex,
+                    col as f32 * tiles.tile_size.x,
+                    row as f32 * tiles.tile_size.y,
+                    WHITE,
+                    params,
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn err(text: &str) -> String {
+        match parse(text) {
+            Err(e) => e,
+            Ok(_) => panic!("'{}' should be an error", text),
+        }
+    }
+
+    #[test]
+    fn parses_built_in_level_files() {
+        assert!(parse(include_str!("levels/banquet.txt")).is_ok());
+    }
+
+    #[test]
+    fn parses_levels() {
+        let level = parse(
+            "# comment\n\
+             size 800 600\n\
+             counter 0 0 100 20\n\
+             pot 50 60 15 # comment\n\
+             stove 10 10 40 40 2\n\
+             conveyor 0 100 200 20 1 0\n",
+        )
+        .unwrap();
+        assert_eq!(level.size, vec2(800.0, 600.0));
+        assert_eq!(level.obstacles.len(), 2);
+        assert!(matches!(level.obstacles[1].kind, Kind::Pot));
+        assert_eq!(level.hazards.len(), 2);
+        assert_eq!(parse("").unwrap().size, vec2(VIEW_WIDTH, VIEW_HEIGHT));
+    }
+
+    #[test]
+    fn rejects_bad_lines() {
+        assert!(err("\ncounter 0 0 10").starts_with("line 2: can't understand"));
+        assert!(err("pot 1 2 big").starts_with("line 1: "));
+        assert!(err("stove 0 0 10 10 1 2 3").contains("can't understand"));
+        assert!(err("shelf 0 0 10 10").contains("can't understand"));
+    }
+
+    #[test]
+    fn rejects_sizes_things_cant_be_kept_in() {
+        for size in ["size -5 10", "size 0 10", "size 10 NaN", "size inf 10"] {
+            assert!(err(size).contains("needs to be more than 0"), "{}", size);
+        }
+    }
+}
@@ -0,0 +1,82 @@
+// the loading screen, and what went wrong loading. this is up before the font's loaded, so it uses
+// macroquad's built in one, and it's in screen pixels rather than the view's.
+use macroquad::prelude::*;
+
+const BAR_WIDTH: f32 = 300.0;
+const BAR_HEIGHT: f32 = 16.0;
+const TEXT_SIZE: f32 = 20.0;
+const ERROR_SIZE: f32 = 18.0;
+const MARGIN: f32 = 20.0;
+
+// a bar `done` of `total` of the way along, with the name of what's loading under it
+pub fn draw(done: usize, total: usize, loading: &str) {
+    set_default_camera();
+    clear_background(BLACK);
+    let x = (screen_width() - BAR_WIDTH) / 2.0;
+    let y = (screen_height() - BAR_HEIGHT) / 2.0;
+    let progress = done as f32 / total.max(1) as f32;
+    draw_rectangle(x, y, BAR_WIDTH * progress, BAR_HEIGHT, WHITE);
+    draw_rectangle_lines(x, y, BAR_WIDTH, BAR_HEIGHT, 2.0, WHITE);
+
+    let text = format!("loading {}", loading);
+    let width = measure_text(&text, None, TEXT_SIZE as u16, 1.0).width;
+    let y = y + BAR_HEIGHT + MARGIN + TEXT_SIZE;
+    draw_text(&text, (screen_width() - width) / 2.0, y, TEXT_SIZE, GRAY);
+}
+
+// lists files the asset pack is `missing`, and `errors`, until a key or mouse button's pressed.
+// doesn't show anything if there's nothing to show
+pub async fn show_errors(missing: &[&str], errors: &[String]) {
+    if missing.is_empty() && errors.is_empty() {
+        return;
+    }
+    loop {
+        set_default_camera();
+        clear_background(BLACK);
+        let mut y = MARGIN;
+        if !missing.is_empty() {
+            let heading = "the asset pack doesn't have these files, so the built in ones are used:";
+            y = section(y, heading, &missing.join(", "));
+        }
+        if !errors.is_empty() {
+            let heading = "some things couldn't be loaded, so stand-ins are used instead:";
+            y = section(y, heading, &errors.join("\n"));
+        }
+        y += TEXT_SIZE;
+        draw_text("press any key to play", MARGIN, y, TEXT_SIZE, WHITE);
+
+        if get_last_key_pressed().is_some() || is_mouse_button_pressed(MouseButton::Left) {
+            return;
+        }
+        next_frame().await;
+    }
+}
+
+// a heading, and `text` under it wrapped to fit the window. returns how far down the window it got
+fn section(mut y: f32, heading: &str, text: &str) -> f32 {
+    y += TEXT_SIZE;
+    draw_text(heading, MARGIN, y, TEXT_SIZE, WHITE);
+    y += MARGIN / 2.0;
+    let width = screen_width() - MARGIN * 2.0;
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split(' ') {
+            let longer = if line.is_empty() {
+                word.to_string()
+            } else {
+                format!("{} {}", line, word)
+            };
+            let fits = measure_text(&longer, None, ERROR_SIZE as u16, 1.0).width <= width;
+            if fits || line.is_empty() {
+                line = longer;
+            } else {
+                y += ERROR_SIZE;
+                draw_text(&line, MARGIN, y, ERROR_SIZE, ORANGE);
+                line = word.to_string();
+            }
+        }
+        y += ERROR_SIZE;
+        draw_text(&line, MARGIN, y, ERROR_SIZE, ORANGE);
+    }
+    y + MARGIN
+}
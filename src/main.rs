@@ -1,35 +1,96 @@
 #![windows_subsystem = "windows"]
+mod anim;
 mod assets;
+mod audio;
+mod camera;
+#[cfg(debug_assertions)]
+mod console;
+mod grid;
+mod hazards;
+mod hud;
+mod json;
+mod level;
+mod loading;
+mod overlay;
+mod pack;
+mod particles;
+mod patterns;
+#[cfg(debug_assertions)]
+mod reload;
+mod settings;
+mod status;
+mod steering;
+mod tiled;
+mod tuning;
+mod tutorial;
 mod waves;
 
+use anim::{Anim, Clip, Sheet};
 use assets::Assets;
+use audio::{Intensity, Mixer, Sfx};
+use camera::{FollowCamera, Juice};
+#[cfg(debug_assertions)]
+use console::Console;
+use grid::Grid;
+use level::Level;
+use overlay::Overlay;
+use particles::Particles;
+use patterns::Pattern;
+use settings::Settings;
+use status::{Effect, Status};
+use steering::Steering;
+use tuning::Tuning;
+use tutorial::Tutorial;
+use waves::{Wave, Waves};
 
-use macroquad::audio::{play_sound_once, PlaySoundParams};
-use macroquad::camera::Camera2D;
 use macroquad::prelude::*;
 use macroquad::rand::gen_range;
+use std::f32::consts::{FRAC_PI_2, TAU};
 
 const TICKS_PER_SEC: i32 = 60;
 const TICK_RATE: f64 = 1.0 / TICKS_PER_SEC as f64;
 const MAX_TIME_BEHIND: f64 = 0.200;
 
-const WORLD_WIDTH: f32 = 1280.0;
-const WORLD_HEIGHT: f32 = 720.0;
+// how much of the world is on screen at once
+const VIEW_WIDTH: f32 = 1280.0;
+const VIEW_HEIGHT: f32 = 720.0;
 
 // don't run faster when moving diagonally.
-const DIAG_SPEED: f32 = 0.7071;
+const DIAG_SPEED: f32 = std::f32::consts::FRAC_1_SQRT_2;
 const PLAYER_RADIUS: f32 = 20.0;
-const PLAYER_WALK_SPEED: f32 = 2.0;
-const PLAYER_ROLL_SPEED: f32 = 6.0;
-const PLAYER_ROLL_TICKS: i32 = 30;
-const PLAYER_ROLL_RECOVERY_TICKS: i32 = 20;
 
-// Knife hitbox, and how far away from the player it is.
+// Knife hitbox. how far away from the player it is is tuning.knife_reach
 const KNIFE_RADIUS: f32 = 25.0;
-const KNIFE_REACH: f32 = 35.0;
 
-const TICKS_BETWEEN_WAVES_MAX: i32 = TICKS_PER_SEC * 3;
-const TICKS_BETWEEN_WAVES_MIN: i32 = TICKS_PER_SEC * 5;
+// cell size for the collision broadphase. a bit over double the biggest radius of anything
+const BROADPHASE_CELL: f32 = 64.0;
+
+// caps on how many of each thing can be alive at once, and how much bigger waves get.
+#[derive(Copy, Clone)]
+struct Limits {
+    lemons: usize,
+    grapes: usize,
+    breads: usize,
+    bullets: usize,
+    wave_mul: u32,
+}
+
+const NORMAL_LIMITS: Limits = Limits {
+    lemons: LEMONS_MAX,
+    grapes: GRAPES_MAX,
+    breads: BREADS_MAX,
+    bullets: BULLETS_MAX,
+    wave_mul: 1,
+};
+
+// swarm mode, started with --swarm on the command line.
+const SWARM_LIMITS: Limits = Limits {
+    lemons: 4000,
+    grapes: 400,
+    breads: 200,
+    bullets: 8000,
+    wave_mul: 25,
+};
 
 fn make_conf() -> Conf {
     Conf {
@@ -46,35 +107,138 @@ fn make_conf() -> Conf {
 
 #[macroquad::main(make_conf)]
 async fn main() {
-    let ass = assets::load().await.unwrap();
-    macroquad::audio::play_sound(
-        ass.bgm,
-        PlaySoundParams {
-            looped: true,
-            volume: 0.125,
-        },
-    );
-
-    let mut tick_time = get_time();
-
-    let mut st = GameState::default();
+    let pack_path = std::env::args().skip_while(|a| a != "--assets").nth(1);
+    let pack = match &pack_path {
+        Some(path) => Some(pack::open(path).await),
+        None => None,
+    };
+    let mut ass = assets::load(pack.as_ref().and_then(|p| p.as_ref().ok())).await;
+    if let Some(Err(e)) = &pack {
+        let e = format!(
+            "couldn't open the asset pack, so the built in assets are used: {}",
+            e
+        );
+        ass.errors.insert(0, e);
+    }
+    // bad levels and tuning go on the error screen too, rather than crashing with nowhere to say why
+    let level_path = std::env::args().skip_while(|a| a != "--level").nth(1);
+    let level = match level::load(level_path.as_deref(), &ass).await {
+        Ok(level) => level,
+        Err(e) => {
+            let e = format!(
+                "couldn't load the level, so the default kitchen is used: {}",
+                e
+            );
+            ass.errors.push(e);
+            level::load(None, &ass)
+                .await
+                .unwrap_or_else(|e| panic!("the built in level is broken: {}", e))
+        }
+    };
+    let tuning_path = std::env::args().skip_while(|a| a != "--tuning").nth(1);
+    let tuning = match &tuning_path {
+        Some(path) => tuning::load(path).await.unwrap_or_else(|e| {
+            let e = format!("couldn't load the tuning, so the defaults are used: {}", e);
+            ass.errors.push(e);
+            Tuning::default()
+        }),
+        None => Tuning::default(),
+    };
+    if !ass.missing.is_empty() {
+        eprintln!(
+            "the asset pack doesn't have these files, so the built in ones are used instead: {}",
+            ass.missing.join(", ")
+        );
+    }
+    for e in &ass.errors {
+        eprintln!("{}", e);
+    }
+    loading::show_errors(&ass.missing, &ass.errors).await;
+    let mut settings = Settings::load();
+    let mut mixer = Mixer::new();
+    mixer.start_music(&ass, &settings);
+
+    // game time, which runs slower than real time in slow motion, and stops for hit-stop and while
+    // paused
+    let mut clock = 0.0;
+    let mut tick_time = clock;
+
+    let limits = if std::env::args().any(|a| a == "--swarm") {
+        SWARM_LIMITS
+    } else {
+        NORMAL_LIMITS
+    };
+    let mut st = GameState::new(limits, level, Waves::built_in(), tuning);
+    if std::env::args().any(|a| a == "--tutorial") {
+        st = st.with_tutorial();
+    }
+    let mut cam = FollowCamera::new(st.player_pos, st.level.size);
+    let mut fx = Particles::new();
+    let mut juice = Juice::new();
+    let mut overlay = Overlay::new();
+    #[cfg(debug_assertions)]
+    let mut console = Console::new();
+    #[cfg(debug_assertions)]
+    let mut watcher = reload::Watcher::new(pack_path.as_deref(), tuning_path.as_deref());
 
     loop {
         // Fixed tick rate game loop, with some support for smoothing out the frame rate if there's
         // any hiccups. This is done by tracking how far 'behind' we are (clamped to a reasonable
         // value), and doing multiple logic steps if needed to catch back up.
-        let now = get_time();
-        if now > tick_time + MAX_TIME_BEHIND {
-            tick_time = now - MAX_TIME_BEHIND;
+        #[cfg(debug_assertions)]
+        if !settings.open {
+            console.update(&mut st, &cam);
+        }
+        // the console's only in debug builds
+        #[cfg(debug_assertions)]
+        let (console_open, console_time_scale) = (console.open, console.time_scale);
+        #[cfg(not(debug_assertions))]
+        let (console_open, console_time_scale) = (false, 1.0);
+        if !console_open {
+            settings.update();
+        }
+        overlay.update();
+        #[cfg(debug_assertions)]
+        watcher
+            .update(
+                &mut ass,
+                &mut mixer,
+                &settings,
+                &mut st.level,
+                &mut st.waves,
+                &mut st.tuning,
+            )
+            .await;
+        mixer.update(&ass, &settings, get_frame_time());
+        if !settings.open && !console_open {
+            juice.update(get_frame_time());
+            let time_scale = juice.time_scale(&settings) * console_time_scale;
+            clock += (get_frame_time() * time_scale) as f64;
+        }
+
+        if clock > tick_time + MAX_TIME_BEHIND {
+            tick_time = clock - MAX_TIME_BEHIND;
         }
 
-        while tick_time < now {
+        while tick_time < clock {
             tick_time += TICK_RATE;
-            tick(&mut st, &ass);
+            tick(&mut st);
+            overlay.ticked();
+            tick_cosmetics(&mut fx, &mut juice, &settings, &st);
+            tick_sounds(&mut mixer, &ass, &settings, &st);
         }
 
+        // how far between the last tick and the next one we are, for drawing things in between
+        let alpha = (1.0 - (tick_time - clock) / TICK_RATE).clamp(0.0, 1.0) as f32;
+
+        let player_pos = st.player_prev_pos.lerp(st.player_pos, alpha);
+        cam.update(player_pos, st.level.size, get_frame_time());
+
         clear_background(BLACK);
-        render(&st, &ass);
+        let world_cam = juice.apply(cam.world_camera(), &settings);
+        render(&st, &ass, &cam, &world_cam, &fx, &overlay, alpha);
+        #[cfg(debug_assertions)]
+        console.draw(ass.font);
         next_frame().await
     }
 }
@@ -87,26 +251,121 @@ enum PlayerState {
     Dead,
 }
 
+// things that happened during a tick, for anything cosmetic to react to. cleared at the start of
+// every tick
+enum Event {
+    Killed(Vec2, Food),
+    // a bullet was fired from here, going this way
+    Shot(Vec2, Vec2),
+    Rolled,
+    // a bread's about to charge from here
+    BreadWarned(Vec2),
+    PlayerDied,
+}
+
 struct GameState {
     game_over: bool,
     tick: i32,
+    score: i32,
 
     next_wave_num: i32,
     next_wave_at_tick: i32,
 
     player_pos: Vec2,
+    // where things were at the end of the previous tick, for drawing between ticks
+    player_prev_pos: Vec2,
     player_dir: Vec2,
     // which tick the player ceases rolling, and starts recovering from the roll
     player_rolling_until: i32,
+    player_anim: Anim,
 
     // knife keeps its own dir, so that it doesn't get set back to 0,0 when hte player stops moving
     knife_pos: Vec2,
+    knife_prev_pos: Vec2,
     knife_dir: Vec2,
 
     lemons: Vec<Lemon>,
     grapes: Vec<Grape>,
     bullets: Vec<Bullet>,
     breads: Vec<Bread>,
+    corpses: Vec<Corpse>,
+    events: Vec<Event>,
+
+    limits: Limits,
+    broadphase: Broadphase,
+    level: Level,
+    waves: Waves,
+    tuning: Tuning,
+    // scripted steps instead of the usual waves, when playing the tutorial
+    tutorial: Option<Tutorial>,
+    // the player can't die. a cheat from the console
+    god: bool,
+}
+
+// one grid per kind of thing. rebuilt at the end of tick_enemies once everything has moved, then used
+// by check_player_death and the next tick's tick_check_enemy_death, before anything is added, moved
+// or removed.
+struct Broadphase {
+    lemons: Grid,
+    grapes: Grid,
+    bullets: Grid,
+    breads: Grid,
+}
+
+impl Broadphase {
+    fn new(world_size: Vec2) -> Self {
+        let grid = || Grid::new(world_size.x, world_size.y, BROADPHASE_CELL);
+        Self {
+            lemons: grid(),
+            grapes: grid(),
+            bullets: grid(),
+            breads: grid(),
+        }
+    }
+
+    fn rebuild(
+        &mut self,
+        lemons: &[Lemon],
+        grapes: &[Grape],
+        bullets: &[Bullet],
+        breads: &[Bread],
+    ) {
+        self.lemons.build(lemons.iter().map(|l| l.pos));
+        self.grapes.build(grapes.iter().map(|g| g.pos));
+        self.bullets.build(bullets.iter().map(|b| b.pos));
+        self.breads.build(breads.iter().map(|b| b.pos));
+    }
+}
+
+// indices of everything in `items` strictly closer than sqrt(`dist_sq`) to `at`, in ascending order.
+fn find_near<T>(
+    items: &[T],
+    grid: &Grid,
+    pos: fn(&T) -> Vec2,
+    at: Vec2,
+    dist_sq: f32,
+) -> Vec<usize> {
+    let mut near = Vec::new();
+    grid.query(at, dist_sq.sqrt(), |i| {
+        if pos(&items[i]).distance_squared(at) < dist_sq {
+            near.push(i);
+        }
+    });
+    near.sort_unstable();
+    near
+}
+
+// removes the given indices from `items`.
+fn remove_indices<T>(items: &mut Vec<T>, dead: &[usize]) {
+    if dead.is_empty() {
+        return;
+    }
+
+    let mut i = 0;
+    items.retain(|_| {
+        i += 1;
+        dead.binary_search(&(i - 1)).is_err()
+    });
 }
 
 impl GameState {
@@ -115,89 +374,193 @@ impl GameState {
             PlayerState::Dead
         } else if self.player_rolling_until > self.tick {
             PlayerState::Roll
-        } else if self.player_rolling_until + PLAYER_ROLL_RECOVERY_TICKS > self.tick {
+        } else if self.player_rolling_until + self.tuning.player_roll_recovery_ticks > self.tick {
             PlayerState::Recover
         } else {
             PlayerState::Walk
         }
     }
 
-    fn spawn_lemon(&mut self) {
-        if self.lemons.len() >= LEMONS_MAX {
+    // spawns at `at`, or if that's None, somewhere not too close to the player. so do the others
+    fn spawn_lemon(&mut self, at: Option<Vec2>) {
+        if self.lemons.len() >= self.limits.lemons {
             return;
         }
 
-        let new_lemon = Lemon::new(rand_spawn_pos(self.player_pos));
+        let pos = at.unwrap_or_else(|| rand_spawn_pos(self.player_pos, &self.level, &self.tuning));
+        let new_lemon = Lemon::new(pos, &self.tuning);
         self.lemons.push(new_lemon);
     }
 
-    fn spawn_grape(&mut self) {
-        if self.grapes.len() >= GRAPES_MAX {
+    fn spawn_grape(&mut self, pattern: Pattern, at: Option<Vec2>) {
+        if self.grapes.len() >= self.limits.grapes {
             return;
         }
 
-        let new_grape = Grape::new(rand_spawn_pos(self.player_pos));
+        let pos = at.unwrap_or_else(|| rand_spawn_pos(self.player_pos, &self.level, &self.tuning));
+        let new_grape = Grape::new(pos, pattern, &self.tuning);
         self.grapes.push(new_grape);
     }
 
-    fn spawn_bread(&mut self) {
-        if self.breads.len() >= BREADS_MAX {
+    // 0 just after a roll starts, filling up to 1 once the player can roll again
+    fn roll_ready(&self) -> f32 {
+        let roll_ticks = self.tuning.player_roll_ticks;
+        let cooldown = roll_ticks + self.tuning.player_roll_recovery_ticks;
+        let since_roll = self.tick - (self.player_rolling_until - roll_ticks);
+        (since_roll as f32 / cooldown as f32).min(1.0)
+    }
+
+    fn player_vel(&self) -> Vec2 {
+        let speed_mul = match self.player_state() {
+            PlayerState::Dead => 0.0,
+            PlayerState::Walk => self.tuning.player_walk_speed,
+            PlayerState::Roll => self.tuning.player_roll_speed,
+            PlayerState::Recover => 0.0,
+        };
+        self.player_dir * speed_mul
+    }
+
+    fn spawn_bread(&mut self, aim: BreadAim, at: Option<Vec2>) {
+        if self.breads.len() >= self.limits.breads {
             return;
         }
 
-        let new_bread = Bread::new(self.player_pos);
+        let pos = at.unwrap_or_else(|| rand_spawn_pos(self.player_pos, &self.level, &self.tuning));
+        let new_bread = Bread::new(pos, aim, &self.tuning);
         self.breads.push(new_bread);
     }
 }
 
-impl Default for GameState {
-    fn default() -> Self {
-        let world_centre = vec2(WORLD_WIDTH / 2.0, WORLD_HEIGHT / 2.0);
+impl GameState {
+    fn new(limits: Limits, level: Level, waves: Waves, tuning: Tuning) -> Self {
+        let start = level.player_start.unwrap_or_else(|| level.centre());
         Self {
             game_over: false,
             tick: 0,
+            score: 0,
 
             next_wave_num: 0,
             next_wave_at_tick: 0,
 
-            player_pos: world_centre,
+            player_pos: start,
+            player_prev_pos: start,
             player_dir: vec2(0.0, 0.0),
             // dirty hack to start the player not in recovery mode
-            player_rolling_until: -PLAYER_ROLL_RECOVERY_TICKS,
+            player_rolling_until: -tuning.player_roll_recovery_ticks,
+            player_anim: Anim::new(Clip::Idle),
 
-            knife_pos: world_centre,
+            knife_pos: start,
+            knife_prev_pos: start,
             knife_dir: vec2(1.0, 0.0),
 
-            lemons: Vec::with_capacity(LEMONS_MAX),
-            grapes: Vec::with_capacity(GRAPES_MAX),
+            lemons: Vec::with_capacity(limits.lemons),
+            grapes: Vec::with_capacity(limits.grapes),
             bullets: Vec::with_capacity(128),
-            breads: Vec::with_capacity(BREADS_MAX),
+            breads: Vec::with_capacity(limits.breads),
+            corpses: Vec::new(),
+            events: Vec::new(),
+
+            limits,
+            broadphase: Broadphase::new(level.size),
+            level,
+            waves,
+            tuning,
+            tutorial: None,
+            god: false,
+        }
+    }
+
+    // a new game, on the same level with the same waves and tuning as this one
+    fn restart(&self) -> Self {
+        let new = Self::new(
+            self.limits,
+            self.level.clone(),
+            self.waves.clone(),
+            self.tuning,
+        );
+        Self {
+            god: self.god,
+            ..new
+        }
+    }
+
+    fn with_tutorial(self) -> Self {
+        Self {
+            tutorial: Some(Tutorial::new(self.player_pos)),
+            ..self
         }
     }
 }
 
-fn tick(state: &mut GameState, ass: &Assets) {
+fn tick(state: &mut GameState) {
+    state.events.clear();
+    save_prev_positions(state);
+
     if state.game_over {
         if is_key_down(KeyCode::R) {
-            *state = GameState::default();
+            *state = state.restart();
+        } else if is_key_down(KeyCode::T) {
+            *state = state.restart().with_tutorial();
         }
+        // keep animating, so the death animation plays out
+        tick_animations(state);
         return;
     }
 
     state.tick += 1;
 
-    tick_player(state, ass);
+    tick_player(state);
     tick_knife(state);
-    tick_check_enemy_death(state, ass);
-    tick_spawner(state);
-    tick_enemies(state, ass);
+    tick_check_enemy_death(state);
+    if state.tutorial.is_some() {
+        tutorial::tick(state);
+    } else {
+        tick_spawner(state);
+    }
+    tick_hazards(state);
+    tick_enemies(state);
+
+    // rolling gets you over the stove, too
+    let player_dead = check_player_death(state) || state.level.burns(state.player_pos, state.tick);
+    if state.player_state() != PlayerState::Roll && player_dead && !state.god {
+        if state.tutorial.is_some() {
+            // no game overs in the tutorial, just another go at the same step
+            tutorial::retry(state);
+        } else {
+            state.game_over = true;
+            state.events.push(Event::PlayerDied);
+        }
+    }
+
+    for e in &state.events {
+        if let Event::Killed(_, food) = e {
+            state.score += food.points();
+        }
+    }
 
-    if state.player_state() != PlayerState::Roll && check_player_death(state) {
-        state.game_over = true;
+    tick_animations(state);
+}
+
+// done even when nothing's moving, so that everything comes to rest rather than jittering between
+// two positions forever
+fn save_prev_positions(state: &mut GameState) {
+    state.player_prev_pos = state.player_pos;
+    state.knife_prev_pos = state.knife_pos;
+    for l in &mut state.lemons {
+        l.prev_pos = l.pos;
+    }
+    for g in &mut state.grapes {
+        g.prev_pos = g.pos;
+    }
+    for b in &mut state.bullets {
+        b.prev_pos = b.pos;
+    }
+    for b in &mut state.breads {
+        b.prev_pos = b.pos;
     }
 }
 
-fn tick_player(state: &mut GameState, ass: &Assets) {
+fn tick_player(state: &mut GameState) {
     if state.player_state() == PlayerState::Walk || state.player_state() == PlayerState::Recover {
         let up = is_key_down(KeyCode::W) || is_key_down(KeyCode::Up);
         let left = is_key_down(KeyCode::A) || is_key_down(KeyCode::Left);
@@ -219,425 +582,1011 @@ fn tick_player(state: &mut GameState, ass: &Assets) {
         let start_roll =
             is_key_down(KeyCode::Space) && state.player_state() != PlayerState::Recover;
         if start_roll {
-            state.player_rolling_until = state.tick + PLAYER_ROLL_TICKS;
-            play_sound_once(ass.roll);
+            state.player_rolling_until = state.tick + state.tuning.player_roll_ticks;
+            state.events.push(Event::Rolled);
         }
     }
-    let speed_mul = match state.player_state() {
-        PlayerState::Dead => 0.0,
-        PlayerState::Walk => PLAYER_WALK_SPEED,
-        PlayerState::Roll => PLAYER_ROLL_SPEED,
-        PlayerState::Recover => 0.0,
-    };
-    state.player_pos += state.player_dir * speed_mul;
+    state.player_pos += state.player_vel();
+    state.player_pos += state.level.conveyor_push(state.player_pos);
 
-    ensure_in_bounds(&mut state.player_pos);
+    state.level.push_out(&mut state.player_pos, PLAYER_RADIUS);
+    state.level.keep_in_bounds(&mut state.player_pos);
 }
 
 fn tick_knife(state: &mut GameState) {
     if state.player_dir != vec2(0.0, 0.0) {
         state.knife_dir = state.player_dir
     };
-    state.knife_pos = state.player_pos + state.knife_dir * KNIFE_REACH;
+    state.knife_pos = state.player_pos + state.knife_dir * state.tuning.knife_reach;
 }
 
-fn tick_check_enemy_death(state: &mut GameState, ass: &Assets) {
+fn tick_check_enemy_death(state: &mut GameState) {
     const LEMON_KILL_DIST_SQ: f32 = KNIFE_RADIUS * KNIFE_RADIUS + LEMON_RADIUS * LEMON_RADIUS;
     const GRAPE_KILL_DIST_SQ: f32 = KNIFE_RADIUS * KNIFE_RADIUS + GRAPE_RADIUS * GRAPE_RADIUS;
     const BREAD_KILL_DIST_SQ: f32 = KNIFE_RADIUS * KNIFE_RADIUS + BREAD_RADIUS * BREAD_RADIUS;
 
     let kill_zone = state.knife_pos;
+    let bp = &state.broadphase;
+
+    // find everything first, the grids are out of date as soon as anything's removed
+    let dead_lemons = find_near(
+        &state.lemons,
+        &bp.lemons,
+        |l| l.pos,
+        kill_zone,
+        LEMON_KILL_DIST_SQ,
+    );
+    let dead_grapes = find_near(
+        &state.grapes,
+        &bp.grapes,
+        |g| g.pos,
+        kill_zone,
+        GRAPE_KILL_DIST_SQ,
+    );
+    let dead_breads = find_near(
+        &state.breads,
+        &bp.breads,
+        |b| b.pos,
+        kill_zone,
+        BREAD_KILL_DIST_SQ,
+    );
 
-    let initial_lem_len = state.lemons.len();
-    let initial_grape_len = state.grapes.len();
-    let initial_bread_len = state.breads.len();
-
-    state
-        .lemons
-        .retain(|l| l.pos.distance_squared(kill_zone) > LEMON_KILL_DIST_SQ);
-
-    state
-        .grapes
-        .retain(|g| g.pos.distance_squared(kill_zone) > GRAPE_KILL_DIST_SQ);
+    for &i in &dead_lemons {
+        kill(
+            state.lemons[i].corpse(&state.tuning),
+            &mut state.corpses,
+            &mut state.events,
+        );
+    }
+    for &i in &dead_grapes {
+        kill(
+            state.grapes[i].corpse(),
+            &mut state.corpses,
+            &mut state.events,
+        );
+    }
+    for &i in &dead_breads {
+        kill(
+            state.breads[i].corpse(),
+            &mut state.corpses,
+            &mut state.events,
+        );
+    }
 
-    state
-        .breads
-        .retain(|b| b.pos.distance_squared(kill_zone) > BREAD_KILL_DIST_SQ);
+    remove_indices(&mut state.lemons, &dead_lemons);
+    remove_indices(&mut state.grapes, &dead_grapes);
+    remove_indices(&mut state.breads, &dead_breads);
 
-    // bullets aren't killed by knife, but when going out of bounds
+    // bullets aren't killed by knife, but when going out of bounds or hitting an obstacle
+    let level = &state.level;
     state
         .bullets
-        .retain(|b| b.pos >= vec2(0.0, 0.0) && b.pos <= vec2(WORLD_WIDTH, WORLD_HEIGHT));
-
-    let any_lemons_died = state.lemons.len() != initial_lem_len;
-    let any_grapes_died = state.grapes.len() != initial_grape_len;
-    let any_breads_died = state.breads.len() != initial_bread_len;
-
-    if any_lemons_died || any_grapes_died || any_breads_died {
-        play_sound_once(ass.enemy_death);
-    }
+        .retain(|b| level.in_bounds(b.pos) && !level.overlaps(b.pos, BULLET_RADIUS));
 }
 
 fn tick_spawner(state: &mut GameState) {
-    let spawn_wave = state.tick >= state.next_wave_at_tick;
-    if !spawn_wave {
+    let wave_due = state.tick >= state.next_wave_at_tick;
+    if !wave_due {
         return;
     }
 
-    let nw = waves::next_wave(state.next_wave_num);
+    let nw = state.waves.next_wave(state.next_wave_num);
     state.next_wave_num += 1;
+    let t = &state.tuning;
     state.next_wave_at_tick =
-        state.tick + gen_range(TICKS_BETWEEN_WAVES_MIN, TICKS_BETWEEN_WAVES_MAX);
+        state.tick + gen_range(t.ticks_between_waves_min, t.ticks_between_waves_max);
+    spawn_wave(state, &nw);
+}
 
-    let num_lemons = gen_range(nw.lemons.0, nw.lemons.1);
-    let num_grapes = gen_range(nw.grapes.0, nw.grapes.1);
-    let num_breads = gen_range(nw.breads.0, nw.breads.1);
+fn spawn_wave(state: &mut GameState, nw: &Wave) {
+    let mul = state.limits.wave_mul;
+    let num_lemons = gen_range(nw.lemons.0, nw.lemons.1) as u32 * mul;
+    let num_grapes = gen_range(nw.grapes.0, nw.grapes.1) as u32 * mul;
+    let num_breads = gen_range(nw.breads.0, nw.breads.1) as u32 * mul;
+    let grape_pattern = patterns::get(nw.grape_pattern);
 
     for _ in 0..num_lemons {
-        state.spawn_lemon();
+        state.spawn_lemon(None);
     }
 
     for _ in 0..num_grapes {
-        state.spawn_grape();
+        state.spawn_grape(grape_pattern, None);
     }
 
     for _ in 0..num_breads {
-        state.spawn_bread();
+        state.spawn_bread(nw.bread_aim, None);
     }
 }
 
-fn tick_enemies(state: &mut GameState, ass: &Assets) {
+// conveyors push enemies along, and stoves set them on fire. the player's handled in tick_player and
+// tick.
+fn tick_hazards(state: &mut GameState) {
+    let level = &state.level;
+    let tick = state.tick;
+    let burn_ticks = state.tuning.hazard_burn_ticks;
+    let affect = |pos: &mut Vec2, radius: f32, status: &mut Status| {
+        let push = level.conveyor_push(*pos);
+        if push != Vec2::ZERO {
+            *pos += push;
+            level.push_out(pos, radius);
+            level.keep_in_bounds(pos);
+        }
+        if level.burns(*pos, tick) {
+            status.apply(Effect::Burning, burn_ticks);
+        }
+    };
+
     for l in &mut state.lemons {
-        l.tick(state.player_pos);
+        affect(&mut l.pos, LEMON_RADIUS, &mut l.status);
     }
-
     for g in &mut state.grapes {
-        g.tick(ass, state.player_pos, &mut state.bullets);
+        affect(&mut g.pos, GRAPE_RADIUS, &mut g.status);
+    }
+    for b in &mut state.breads {
+        affect(&mut b.pos, BREAD_RADIUS, &mut b.status);
     }
+}
 
-    for b in &mut state.bullets {
-        b.tick();
+// counts down status effects. anything that burns up is removed, leaving its corpse behind
+fn tick_statuses<T>(
+    items: &mut Vec<T>,
+    corpses: &mut Vec<Corpse>,
+    events: &mut Vec<Event>,
+    status: fn(&mut T) -> &mut Status,
+    corpse: impl Fn(&T) -> Corpse,
+) {
+    items.retain_mut(|item| {
+        let burnt_up = status(item).tick();
+        if burnt_up {
+            kill(corpse(item), corpses, events);
+        }
+        !burnt_up
+    });
+}
+
+// leaves a corpse where an enemy died. removing the enemy itself is up to the caller
+fn kill(corpse: Corpse, corpses: &mut Vec<Corpse>, events: &mut Vec<Event>) {
+    events.push(Event::Killed(corpse.pos, corpse.food));
+    corpses.push(corpse);
+}
+
+// runs once after every tick, but only ever reads the game state
+fn tick_cosmetics(fx: &mut Particles, juice: &mut Juice, settings: &Settings, state: &GameState) {
+    const KILL_TRAUMA: f32 = 0.2;
+    const DEATH_TRAUMA: f32 = 0.7;
+
+    let mut kills = 0;
+    for e in &state.events {
+        match *e {
+            Event::Killed(pos, food) => {
+                fx.juice(pos, food.juice());
+                kills += 1;
+            }
+            Event::Shot(pos, dir) => fx.puff(pos, dir),
+            Event::Rolled | Event::BreadWarned(_) => {}
+            Event::PlayerDied => {
+                juice.add_trauma(DEATH_TRAUMA);
+                juice.player_died(state.player_pos);
+            }
+        }
+    }
+    if kills > 0 {
+        juice.add_trauma(KILL_TRAUMA * kills as f32);
+        juice.multi_kill(kills, settings);
+    }
+    if !state.game_over {
+        juice.player_alive();
     }
 
-    for b in &mut state.breads {
-        b.tick(state.player_pos, ass);
+    if state.player_state() == PlayerState::Roll {
+        fx.dust(state.player_pos, state.player_vel());
     }
+    fx.tick();
 }
 
-fn check_player_death(state: &GameState) -> bool {
-    const LEMON_KILL_DIST_SQ: f32 = PLAYER_RADIUS * PLAYER_RADIUS + LEMON_RADIUS * LEMON_RADIUS;
-    const BULLET_KILL_DIST_SQ: f32 = PLAYER_RADIUS * PLAYER_RADIUS + BULLET_RADIUS * BULLET_RADIUS;
-    const BREAD_KILL_DIST_SQ: f32 = PLAYER_RADIUS * PLAYER_RADIUS + BREAD_RADIUS * BREAD_RADIUS;
+// like tick_cosmetics, for sound effects and music. the mixer stops sound effects piling up when lots
+// happen at once
+fn tick_sounds(mixer: &mut Mixer, ass: &Assets, settings: &Settings, state: &GameState) {
+    // the heartbeat starts when anything's this close to touching the player, and gets louder
+    // the closer it gets
+    const DANGER_RANGE: f32 = 120.0;
+
+    for e in &state.events {
+        let (sfx, pos) = match *e {
+            Event::Killed(pos, _) => (Sfx::EnemyDeath, pos),
+            Event::Shot(pos, _) => (Sfx::EnemyShoot, pos),
+            Event::Rolled => (Sfx::Roll, state.player_pos),
+            Event::BreadWarned(pos) => (Sfx::BreadAttack, pos),
+            Event::PlayerDied => {
+                mixer.game_over(ass, settings);
+                continue;
+            }
+        };
+        mixer.play(ass, settings, sfx, pos - state.player_pos);
+    }
 
-    let kill_zone = state.player_pos;
+    // distance between the edges of the player and the nearest thing that can kill them
+    let gap = |pos: Vec2, radius: f32| state.player_pos.distance(pos) - PLAYER_RADIUS - radius;
+    let nearest = state
+        .lemons
+        .iter()
+        .map(|l| gap(l.pos, LEMON_RADIUS))
+        .chain(state.breads.iter().map(|b| gap(b.pos, BREAD_RADIUS)))
+        .chain(state.bullets.iter().map(|b| gap(b.pos, BULLET_RADIUS)))
+        .fold(f32::INFINITY, f32::min);
+
+    mixer.set_intensity(&Intensity {
+        enemies: state.lemons.len() + state.grapes.len() + state.breads.len(),
+        heavy: !state.breads.is_empty(),
+        danger: (1.0 - nearest / DANGER_RANGE).clamp(0.0, 1.0),
+        game_over: state.game_over,
+    });
+}
 
-    for l in &state.lemons {
-        if l.pos.distance_squared(kill_zone) < LEMON_KILL_DIST_SQ {
-            return true;
+fn tick_animations(state: &mut GameState) {
+    let player_clip = match state.player_state() {
+        PlayerState::Walk if state.player_dir != vec2(0.0, 0.0) => Clip::Walk,
+        PlayerState::Walk | PlayerState::Recover => Clip::Idle,
+        PlayerState::Roll => Clip::Roll,
+        PlayerState::Dead => Clip::Death,
+    };
+    state.player_anim.tick(player_clip);
+
+    // frozen things stop animating too
+    for l in &mut state.lemons {
+        if !l.status.is_frozen() {
+            let clip = if l.status.speed_mul() > 0.0 {
+                Clip::Walk
+            } else {
+                Clip::Idle
+            };
+            l.anim.tick(clip);
         }
     }
-
-    for b in &state.bullets {
-        if b.pos.distance_squared(kill_zone) < BULLET_KILL_DIST_SQ {
-            return true;
+    for g in &mut state.grapes {
+        if !g.status.is_frozen() {
+            g.anim.tick(Clip::Idle);
         }
     }
-
-    for b in &state.breads {
-        if b.pos.distance_squared(kill_zone) < BREAD_KILL_DIST_SQ {
-            return true;
+    for b in &mut state.breads {
+        if !b.status.is_frozen() {
+            let clip = if b.is_charging() {
+                Clip::Walk
+            } else {
+                Clip::Idle
+            };
+            b.anim.tick(clip);
         }
     }
 
-    false
+    for c in &mut state.corpses {
+        c.anim.tick(Clip::Death);
+    }
+    state.corpses.retain(|c| !c.anim.finished());
+}
+
+fn tick_enemies(state: &mut GameState) {
+    let tuning = &state.tuning;
+    let corpses = &mut state.corpses;
+    let events = &mut state.events;
+    tick_statuses(
+        &mut state.lemons,
+        corpses,
+        events,
+        |l| &mut l.status,
+        |l| l.corpse(tuning),
+    );
+    tick_statuses(
+        &mut state.grapes,
+        corpses,
+        events,
+        |g| &mut g.status,
+        Grape::corpse,
+    );
+    tick_statuses(
+        &mut state.breads,
+        corpses,
+        events,
+        |b| &mut b.status,
+        Bread::corpse,
+    );
+
+    // grapes never move, so the crowd treats them as obstacles to walk around along with the level
+    let bp = &mut state.broadphase;
+    bp.lemons.build(state.lemons.iter().map(|l| l.pos));
+    bp.grapes.build(state.grapes.iter().map(|g| g.pos));
+
+    let lemon_positions: Vec<Vec2> = state.lemons.iter().map(|l| l.pos).collect();
+    let mut neighbours = Vec::new();
+    let mut obstacles = Vec::new();
+    for (me, l) in state.lemons.iter_mut().enumerate() {
+        neighbours.clear();
+        bp.lemons.query(l.pos, LEMON_NEIGHBOUR_RANGE, |i| {
+            if i != me {
+                neighbours.push(lemon_positions[i])
+            }
+        });
+
+        obstacles.clear();
+        bp.grapes
+            .query(l.pos, LEMON_NEIGHBOUR_RANGE + GRAPE_RADIUS, |i| {
+                obstacles.push((state.grapes[i].pos, GRAPE_RADIUS))
+            });
+        state
+            .level
+            .nearby_circles(l.pos, LEMON_NEIGHBOUR_RANGE, &mut obstacles);
+
+        l.tick(
+            state.player_pos,
+            &state.level,
+            &neighbours,
+            &obstacles,
+            tuning,
+        );
+    }
+
+    for g in &mut state.grapes {
+        g.tick(
+            state.player_pos,
+            &mut state.bullets,
+            &mut state.events,
+            tuning,
+        );
+    }
+
+    for b in &mut state.bullets {
+        b.tick(state.player_pos);
+    }
+
+    let player_vel = state.player_vel();
+    let bread_positions: Vec<Vec2> = state.breads.iter().map(|b| b.pos).collect();
+    for (me, b) in state.breads.iter_mut().enumerate() {
+        neighbours.clear();
+        let others = bread_positions.iter().enumerate().filter(|(i, _)| *i != me);
+        neighbours.extend(others.map(|(_, pos)| *pos));
+        b.tick(
+            state.player_pos,
+            player_vel,
+            &state.level,
+            &neighbours,
+            &mut state.events,
+            tuning,
+        );
+    }
+
+    // drop the newest bullets if there's too many
+    state.bullets.truncate(state.limits.bullets);
+
+    state
+        .broadphase
+        .rebuild(&state.lemons, &state.grapes, &state.bullets, &state.breads);
+}
+
+fn check_player_death(state: &GameState) -> bool {
+    let bp = &state.broadphase;
+    touching_player(state, &state.lemons, &bp.lemons, |l| l.pos, LEMON_RADIUS)
+        || touching_player(state, &state.bullets, &bp.bullets, |b| b.pos, BULLET_RADIUS)
+        || touching_player(state, &state.breads, &bp.breads, |b| b.pos, BREAD_RADIUS)
+}
+
+fn touching_player<T>(
+    state: &GameState,
+    items: &[T],
+    grid: &Grid,
+    pos: fn(&T) -> Vec2,
+    radius: f32,
+) -> bool {
+    let kill_dist_sq = PLAYER_RADIUS * PLAYER_RADIUS + radius * radius;
+    !find_near(items, grid, pos, state.player_pos, kill_dist_sq).is_empty()
 }
 
 // an enemy that starts as a lime, wanders for a bit, then begins to charge the player aggressively
 // after turning in to a lemon
+// speeds and timings are in tuning.rs
 const LEMONS_MAX: usize = 64;
-const LEMON_SPEED_WANDER: f32 = 0.5;
-const LEMON_WANDER_CLOSE: f32 = 10.0;
-const LEMON_WANDER_SQ: f32 = LEMON_WANDER_CLOSE * LEMON_WANDER_CLOSE;
-const LEMON_SPEED_ATTACK: f32 = 1.8;
-const LEMON_ATTACKS_AFTER_MIN: i32 = TICKS_PER_SEC * 3;
-const LEMON_ATTACKS_AFTER_MAX: i32 = TICKS_PER_SEC * 20;
 const LEMON_RADIUS: f32 = 10.0;
+// how far away other lemons and obstacles are looked for when steering
+const LEMON_NEIGHBOUR_RANGE: f32 = LEMON_RADIUS * 4.0;
 struct Lemon {
     pos: Vec2,
+    prev_pos: Vec2,
     wander_to: Vec2,
     attacks_in: i32,
+    flank_angle: f32,
+    status: Status,
+    anim: Anim,
 }
 
 impl Lemon {
-    fn new(spawn_point: Vec2) -> Lemon {
+    fn new(spawn_point: Vec2, tuning: &Tuning) -> Lemon {
         Lemon {
             pos: spawn_point,
+            prev_pos: spawn_point,
             wander_to: spawn_point,
-            attacks_in: gen_range(LEMON_ATTACKS_AFTER_MIN, LEMON_ATTACKS_AFTER_MAX),
+            attacks_in: gen_range(
+                tuning.lemon_attacks_after_min,
+                tuning.lemon_attacks_after_max,
+            ),
+            flank_angle: gen_range(-tuning.lemon_flank_angle, tuning.lemon_flank_angle),
+            status: Status::default(),
+            anim: Anim::new(Clip::Walk),
         }
     }
 
-    fn tick(&mut self, player_pos: Vec2) {
+    fn tick(
+        &mut self,
+        player_pos: Vec2,
+        level: &Level,
+        neighbours: &[Vec2],
+        obstacles: &[(Vec2, f32)],
+        tuning: &Tuning,
+    ) {
+        if self.status.is_frozen() {
+            return;
+        }
+
+        let steering = tuning.lemon_steering();
         if self.is_attacking() {
             // move towards player at attack rate
-            let dir = (player_pos - self.pos).normalize_or_zero();
-            self.pos += dir * LEMON_SPEED_ATTACK;
+            let dir = if steering.flank {
+                let commit = tuning.lemon_flank_commit;
+                steering::flank(self.pos, player_pos, self.flank_angle, commit)
+            } else {
+                (player_pos - self.pos).normalize_or_zero()
+            };
+            let speed = tuning.lemon_speed_attack;
+            self.steer(&steering, dir, speed, level, neighbours, obstacles);
             return;
         }
 
         self.attacks_in -= 1;
-        if self.pos.distance_squared(self.wander_to) < LEMON_WANDER_SQ {
+        if self.pos.distance(self.wander_to) < tuning.lemon_wander_close {
             // try to avoid the player when wandering.
-            self.wander_to = rand_spawn_pos(player_pos);
+            self.wander_to = rand_open_pos(player_pos, level, tuning);
         }
 
-        let dir = (self.wander_to - self.pos).normalize();
-        self.pos += dir * LEMON_SPEED_WANDER;
+        let dir = (self.wander_to - self.pos).normalize_or_zero();
+        let speed = tuning.lemon_speed_wander;
+        self.steer(&steering, dir, speed, level, neighbours, obstacles);
+    }
+
+    fn steer(
+        &mut self,
+        steering: &Steering,
+        dir: Vec2,
+        speed: f32,
+        level: &Level,
+        neighbours: &[Vec2],
+        obstacles: &[(Vec2, f32)],
+    ) {
+        let push = steering.push(self.pos, LEMON_RADIUS, dir, neighbours, obstacles);
+        let dir = (dir + push).normalize_or_zero();
+        self.pos += dir * speed * self.status.speed_mul();
+        level.push_out(&mut self.pos, LEMON_RADIUS);
+        level.keep_in_bounds(&mut self.pos);
     }
 
     fn is_attacking(&self) -> bool {
         self.attacks_in == 0
     }
+
+    // 0 while still a lime, rising to 1 over the last lemon_ripen_ticks before attacking.
+    fn ripeness(&self, tuning: &Tuning) -> f32 {
+        if self.is_attacking() {
+            return 1.0;
+        }
+        (1.0 - self.attacks_in as f32 / tuning.lemon_ripen_ticks as f32).max(0.0)
+    }
+
+    fn corpse(&self, tuning: &Tuning) -> Corpse {
+        let food = if self.ripeness(tuning) < 0.5 {
+            Food::Lime
+        } else {
+            Food::Lemon
+        };
+        Corpse::new(self.pos, food)
+    }
 }
 
 const GRAPES_MAX: usize = 32;
-const GRAPE_ATTACKS_AFTER_MIN: i32 = 5 * TICKS_PER_SEC;
-const GRAPE_ATTACKS_AFTER_MAX: i32 = 15 * TICKS_PER_SEC;
 const GRAPE_RADIUS: f32 = 25.0;
-const GRAPE_NO_SHOOT_WITHIN: f32 = 100.0;
-const GRAPE_NO_SHOOT_WITHIN_SQ: f32 = GRAPE_NO_SHOOT_WITHIN * GRAPE_NO_SHOOT_WITHIN;
 struct Grape {
     pos: Vec2,
+    prev_pos: Vec2,
     attacks_in: i32,
+    pattern: Pattern,
+    // shots left in the volley currently being fired, and ticks until the next of them
+    shots_left: u8,
+    next_shot_in: i32,
+    status: Status,
+    anim: Anim,
 }
 
 impl Grape {
-    fn new(spawn_point: Vec2) -> Self {
+    fn new(spawn_point: Vec2, pattern: Pattern, tuning: &Tuning) -> Self {
         Self {
             pos: spawn_point,
-            attacks_in: gen_range(GRAPE_ATTACKS_AFTER_MIN, GRAPE_ATTACKS_AFTER_MAX),
+            prev_pos: spawn_point,
+            attacks_in: gen_range(
+                tuning.grape_attacks_after_min,
+                tuning.grape_attacks_after_max,
+            ),
+            pattern,
+            shots_left: 0,
+            next_shot_in: 0,
+            status: Status::default(),
+            anim: Anim::new(Clip::Idle),
         }
     }
 
-    fn tick(&mut self, ass: &Assets, player_pos: Vec2, bullets: &mut Vec<Bullet>) {
+    fn corpse(&self) -> Corpse {
+        Corpse::new(self.pos, Food::Grape)
+    }
+
+    fn tick(
+        &mut self,
+        player_pos: Vec2,
+        bullets: &mut Vec<Bullet>,
+        events: &mut Vec<Event>,
+        tuning: &Tuning,
+    ) {
+        if self.status.is_frozen() {
+            return;
+        }
+
+        if self.shots_left > 0 {
+            self.next_shot_in -= 1;
+            if self.next_shot_in <= 0 {
+                self.shoot(player_pos, bullets, events, tuning);
+            }
+            return;
+        }
+
         self.attacks_in -= 1;
         if self.attacks_in > 0 {
             return;
         }
 
-        self.attacks_in = gen_range(GRAPE_ATTACKS_AFTER_MIN, GRAPE_ATTACKS_AFTER_MAX);
+        self.attacks_in = gen_range(
+            tuning.grape_attacks_after_min,
+            tuning.grape_attacks_after_max,
+        );
+        self.shots_left = self.pattern.shots;
+        self.shoot(player_pos, bullets, events, tuning);
+    }
+
+    fn shoot(
+        &mut self,
+        player_pos: Vec2,
+        bullets: &mut Vec<Bullet>,
+        events: &mut Vec<Event>,
+        tuning: &Tuning,
+    ) {
+        let shot = self.pattern.shots - self.shots_left;
+        self.shots_left -= 1;
+        self.next_shot_in = self.pattern.shot_gap;
+
+        // stunned grapes lose the shot
+        if !self.status.can_attack() {
+            return;
+        }
 
         // if the player is fairly close to the grape, don't shoot. avoids 'instantly' killing the
         // player.
-        if self.pos.distance_squared(player_pos) < GRAPE_NO_SHOOT_WITHIN_SQ {
+        if self.pos.distance(player_pos) < tuning.grape_no_shoot_within {
             return;
         }
 
-        let target_off = vec2(
-            gen_range(-BULLET_RNG, BULLET_RNG),
-            gen_range(-BULLET_RNG, BULLET_RNG),
-        );
-        let target_pos = player_pos + target_off;
-        let bull_dir = (target_pos - self.pos).normalize();
-
-        bullets.push(Bullet {
-            pos: self.pos,
-            dir: bull_dir * BULLET_SPEED,
-        });
-
-        play_sound_once(ass.enemy_shoot);
+        let mut dirs = Vec::new();
+        self.pattern
+            .directions(shot, self.pos, player_pos, &mut dirs);
+        for bull_dir in dirs {
+            bullets.push(Bullet {
+                pos: self.pos,
+                prev_pos: self.pos,
+                dir: bull_dir * tuning.bullet_speed * self.status.speed_mul(),
+                home_in: self.pattern.home_after,
+            });
+            events.push(Event::Shot(self.pos, bull_dir));
+        }
     }
 }
 
-// inaccuracy when shooting bullets from grapes
-const BULLET_RNG: f32 = 60.0;
+const BULLETS_MAX: usize = 1024;
 const BULLET_RADIUS: f32 = 13.0;
-const BULLET_SPEED: f32 = 5.0;
 struct Bullet {
     pos: Vec2,
+    prev_pos: Vec2,
     dir: Vec2,
+    // ticks until the bullet turns to face the player. 0 if it never does
+    home_in: i32,
 }
 
 impl Bullet {
-    fn tick(&mut self) {
+    fn tick(&mut self, player_pos: Vec2) {
         self.pos += self.dir;
+
+        if self.home_in > 0 {
+            self.home_in -= 1;
+            if self.home_in == 0 {
+                let speed = self.dir.length();
+                self.dir = (player_pos - self.pos).normalize_or_zero() * speed;
+            }
+        }
     }
 }
 
 const BREADS_MAX: usize = 10;
 const BREAD_RADIUS: f32 = 20.0;
-const BREAD_IDLE_MIN: i32 = 4 * TICKS_PER_SEC;
-const BREAD_IDLE_MAX: i32 = 6 * TICKS_PER_SEC;
-const BREAD_CHANGE_WARN_TICKS: i32 = 3 * TICKS_PER_SEC;
-const BREAD_SPEED: f32 = 8.0;
-const BREAD_SPEED_SQ: f32 = BREAD_SPEED * BREAD_SPEED;
+// how far the bread sprite shakes just before charging, in pixels
+const BREAD_SHAKE: f32 = 4.0;
+
+// how a bread picks where to charge to
+#[derive(Copy, Clone)]
+enum BreadAim {
+    // wherever the player is when the charge starts
+    Player,
+    // wherever the player was when the warning started. the telegraphed line is exactly where it'll
+    // go, so this is the easiest to read
+    LockOnWarn,
+    // where the player will be if they keep moving the same way, when the charge starts
+    Lead,
+}
 
 struct Bread {
     pos: Vec2,
+    prev_pos: Vec2,
     ticks_until_charge: i32,
+    // while winding up, where the bread is currently aiming. while charging, where it's going
     attacking: Vec2,
+    aim: BreadAim,
+    status: Status,
+    anim: Anim,
 }
 
 impl Bread {
-    fn new(player_pos: Vec2) -> Self {
+    fn new(spawn_point: Vec2, aim: BreadAim, tuning: &Tuning) -> Self {
         Self {
-            pos: rand_spawn_pos(player_pos),
-            ticks_until_charge: BREAD_IDLE_MIN,
+            pos: spawn_point,
+            prev_pos: spawn_point,
+            ticks_until_charge: tuning.bread_idle_min,
             attacking: vec2(0.0, 0.0),
+            aim,
+            status: Status::default(),
+            anim: Anim::new(Clip::Idle),
         }
     }
 
-    fn tick(&mut self, player_pos: Vec2, ass: &Assets) {
+    fn tick(
+        &mut self,
+        player_pos: Vec2,
+        player_vel: Vec2,
+        level: &Level,
+        neighbours: &[Vec2],
+        events: &mut Vec<Event>,
+        tuning: &Tuning,
+    ) {
+        if self.status.is_frozen() {
+            return;
+        }
+
+        let speed_mul = self.status.speed_mul();
+        let warn_ticks = tuning.bread_charge_warn_ticks;
         if self.ticks_until_charge > 0 {
+            if self.ticks_until_charge > warn_ticks {
+                let steering = tuning.bread_steering();
+                let push = steering.push(self.pos, BREAD_RADIUS, Vec2::ZERO, neighbours, &[]);
+                self.pos += push.clamp_length_max(1.0) * tuning.bread_drift_speed * speed_mul;
+                level.push_out(&mut self.pos, BREAD_RADIUS);
+                level.keep_in_bounds(&mut self.pos);
+            }
+
             self.ticks_until_charge -= 1;
-            if self.ticks_until_charge == BREAD_CHANGE_WARN_TICKS {
-                play_sound_once(ass.bread_attack);
-            } else if self.ticks_until_charge == 0 {
+            if self.ticks_until_charge == warn_ticks {
+                events.push(Event::BreadWarned(self.pos));
                 self.attacking = player_pos;
+            } else if self.ticks_until_charge < warn_ticks {
+                self.attacking = match self.aim {
+                    BreadAim::Player => player_pos,
+                    BreadAim::LockOnWarn => self.attacking,
+                    BreadAim::Lead => {
+                        let speed = tuning.bread_speed;
+                        let mut hit = lead_target(self.pos, player_pos, player_vel, speed);
+                        level.keep_in_bounds(&mut hit);
+                        hit
+                    }
+                };
             }
             return;
         }
 
-        // otherwise, attacking. move towards target position at bread_speed.
-        if self.pos.distance_squared(self.attacking) >= BREAD_SPEED_SQ {
+        // otherwise, attacking. move towards target position at bread_speed. stunned breads stop
+        // in place until they recover.
+        let speed = tuning.bread_speed * speed_mul;
+        if speed == 0.0 {
+            return;
+        }
+
+        if self.pos.distance_squared(self.attacking) >= speed * speed {
             let dir = (self.attacking - self.pos).normalize();
-            self.pos += dir * BREAD_SPEED;
+            self.pos += dir * speed;
+
+            // ran in to something. stop here rather than grinding against it
+            if level.push_out(&mut self.pos, BREAD_RADIUS) {
+                self.ticks_until_charge = gen_range(tuning.bread_idle_min, tuning.bread_idle_max);
+            }
         } else {
             // if we're within one tick of target, just teleport there and go back to being idle.
             self.pos = self.attacking;
-            self.ticks_until_charge = gen_range(BREAD_IDLE_MIN, BREAD_IDLE_MAX);
+            self.ticks_until_charge = gen_range(tuning.bread_idle_min, tuning.bread_idle_max);
         }
     }
+
+    // 0 while idle, rising to 1 as the bread is about to charge.
+    fn wind_up(&self, tuning: &Tuning) -> f32 {
+        let warn_ticks = tuning.bread_charge_warn_ticks;
+        if self.ticks_until_charge <= 0 || self.ticks_until_charge > warn_ticks {
+            return 0.0;
+        }
+        1.0 - self.ticks_until_charge as f32 / warn_ticks as f32
+    }
+
+    fn is_charging(&self) -> bool {
+        self.ticks_until_charge <= 0
+    }
+
+    fn corpse(&self) -> Corpse {
+        Corpse::new(self.pos, Food::Bread)
+    }
 }
 
-fn ensure_in_bounds(pos: &mut Vec2) {
-    pos.x = pos.x.clamp(0.0, WORLD_WIDTH);
-    pos.y = pos.y.clamp(0.0, WORLD_HEIGHT);
+#[derive(Copy, Clone)]
+enum Food {
+    Lime,
+    Lemon,
+    Grape,
+    Bread,
 }
 
-fn rand_spawn_pos(avoid_pos: Vec2) -> Vec2 {
-    const TOO_CLOSE: f32 = 250.0;
-    const TOO_CLOSE_SQ: f32 = TOO_CLOSE * TOO_CLOSE;
+impl Food {
+    fn sheet(self, ass: &Assets) -> &Sheet {
+        match self {
+            Food::Lime => &ass.lime,
+            Food::Lemon => &ass.lemon,
+            Food::Grape => &ass.grape,
+            Food::Bread => &ass.bread,
+        }
+    }
 
-    loop {
-        let v = vec2(gen_range(0.0, WORLD_WIDTH), gen_range(0.0, WORLD_HEIGHT));
+    fn points(self) -> i32 {
+        match self {
+            Food::Lime | Food::Lemon => 10,
+            Food::Grape => 25,
+            Food::Bread => 20,
+        }
+    }
 
-        if v.distance_squared(avoid_pos) > TOO_CLOSE_SQ {
-            return v;
+    fn juice(self) -> Color {
+        match self {
+            Food::Lime => Color::new(0.55, 0.8, 0.2, 0.9),
+            Food::Lemon => Color::new(1.0, 0.85, 0.2, 0.9),
+            Food::Grape => Color::new(0.5, 0.15, 0.55, 0.9),
+            // crumbs, rather than juice
+            Food::Bread => Color::new(0.85, 0.65, 0.4, 1.0),
+        }
+    }
+
+    fn radius(self) -> f32 {
+        match self {
+            Food::Lime | Food::Lemon => LEMON_RADIUS,
+            Food::Grape => GRAPE_RADIUS,
+            Food::Bread => BREAD_RADIUS,
         }
     }
 }
 
-fn render(state: &GameState, ass: &Assets) {
-    let mut x = Camera2D::from_display_rect(Rect {
-        x: 0.0,
-        y: 0.0,
-        w: WORLD_WIDTH,
-        h: WORLD_HEIGHT,
-    });
+// left behind by an enemy when it dies, just long enough to play its death animation. doesn't
+// collide with anything
+struct Corpse {
+    pos: Vec2,
+    food: Food,
+    anim: Anim,
+}
 
-    // FUTURE: set viewport to maintain a constant aspect ratio, rather than stretching.
-    const TARGET_ASPECT: f32 = WORLD_WIDTH as f32 / WORLD_HEIGHT as f32;
-    let window_aspect = screen_width() / screen_height();
-    let viewport = if window_aspect < TARGET_ASPECT {
-        // window is squarer than we want, need to add borders at top and bottom
-        let sw = screen_width();
-        let sh = sw / TARGET_ASPECT;
-        let excess_height = (screen_height() - sh) as i32;
-        (0, excess_height / 2, sw as i32, sh as i32)
-    } else {
-        // window is wider than we want. add borders at side
-        let sh = screen_height();
-        let sw = sh * TARGET_ASPECT;
-        let excess_width = (screen_width() - sw) as i32;
-        (excess_width / 2, 0, sw as i32, sh as i32)
-    };
-    x.viewport = Some(viewport);
-    macroquad::camera::set_camera(&x);
+impl Corpse {
+    fn new(pos: Vec2, food: Food) -> Self {
+        Self {
+            pos,
+            food,
+            anim: Anim::new(Clip::Death),
+        }
+    }
+}
 
-    draw_texture(ass.background, 0.0, 0.0, WHITE);
+// where to aim something moving at `speed` from `from` so that it meets a target currently at
+// `target` moving at `target_vel`. if it can't catch up, aim at where the target is now.
+fn lead_target(from: Vec2, target: Vec2, target_vel: Vec2, speed: f32) -> Vec2 {
+    // solve |target + target_vel * t - from| = speed * t, for the first t > 0
+    let to = target - from;
+    let a = target_vel.dot(target_vel) - speed * speed;
+    let b = 2.0 * to.dot(target_vel);
+    let c = to.dot(to);
+    let disc = b * b - 4.0 * a * c;
+    if a >= 0.0 || disc < 0.0 {
+        return target;
+    }
 
-    draw_text("WASD to move. Space to roll", 20., 40., 20.0, WHITE);
-    draw_text(
-        "Dice up the evil food with your knife",
-        20.,
-        60.,
-        20.0,
-        WHITE,
+    // a is negative, so this is the positive root
+    let t = (-b - disc.sqrt()) / (2.0 * a);
+    target + target_vel * t
+}
+
+// enough room for the biggest enemy to not be stuck in a wall
+const SPAWN_WALL_CLEARANCE: f32 = GRAPE_RADIUS;
+// how far from a level's spawn point things can appear, so a wave doesn't all land on one spot
+const SPAWN_SCATTER: f32 = 40.0;
+// how many random places rand_open_pos tries before settling for the best of them
+const OPEN_POS_TRIES: usize = 200;
+
+// somewhere for an enemy to spawn. uses the level's spawn points if it has any that aren't too close
+// to `avoid_pos`.
+fn rand_spawn_pos(avoid_pos: Vec2, level: &Level, tuning: &Tuning) -> Vec2 {
+    let points: Vec<Vec2> = level
+        .spawn_points
+        .iter()
+        .copied()
+        .filter(|p| p.distance(avoid_pos) > tuning.spawn_too_close)
+        .collect();
+    if points.is_empty() {
+        return rand_open_pos(avoid_pos, level, tuning);
+    }
+
+    // not totally convinced macroquad's giving up inclusive or exclusive bounds, here
+    let i = gen_range(0, points.len()).min(points.len() - 1);
+    let scatter = vec2(
+        gen_range(-SPAWN_SCATTER, SPAWN_SCATTER),
+        gen_range(-SPAWN_SCATTER, SPAWN_SCATTER),
     );
-    draw_text("Also you have food allergies", 20., 80., 20.0, WHITE);
-    draw_text("Unless you're rolling. Of course", 20., 100., 20.0, WHITE);
+    let mut v = points[i] + scatter;
+    level.push_out(&mut v, SPAWN_WALL_CLEARANCE);
+    level.keep_in_bounds(&mut v);
+    v
+}
+
+// anywhere in the level that isn't in a wall, and isn't too close to `avoid_pos`. small or packed
+// levels might not have anywhere like that, so after OPEN_POS_TRIES this settles for the furthest open
+// place it found, or failing that, the nearest open place to the middle.
+fn rand_open_pos(avoid_pos: Vec2, level: &Level, tuning: &Tuning) -> Vec2 {
+    let mut furthest: Option<Vec2> = None;
+    for _ in 0..OPEN_POS_TRIES {
+        let v = vec2(gen_range(0.0, level.size.x), gen_range(0.0, level.size.y));
+        if level.overlaps(v, SPAWN_WALL_CLEARANCE) {
+            continue;
+        }
+        if v.distance(avoid_pos) > tuning.spawn_too_close {
+            return v;
+        }
+        let dist_sq = v.distance_squared(avoid_pos);
+        if furthest.is_none_or(|f| f.distance_squared(avoid_pos) < dist_sq) {
+            furthest = Some(v);
+        }
+    }
+
+    furthest.unwrap_or_else(|| {
+        let mut v = level.centre();
+        level.push_out(&mut v, SPAWN_WALL_CLEARANCE);
+        level.keep_in_bounds(&mut v);
+        v
+    })
+}
+
+// `world_cam` is `cam` with any shake or zoom on top
+fn render(
+    state: &GameState,
+    ass: &Assets,
+    cam: &FollowCamera,
+    world_cam: &Camera2D,
+    fx: &Particles,
+    overlay: &Overlay,
+    alpha: f32,
+) {
+    macroquad::camera::set_camera(world_cam);
+
+    level::render(&state.level, cam.view_rect(), ass, state.tick);
+
+    for c in &state.corpses {
+        let sheet = c.food.sheet(ass);
+        anim::draw(sheet, &c.anim, c.pos, c.food.radius(), 0.0, false, WHITE);
+    }
+    fx.render(cam.view_rect(), alpha);
 
-    let player_params = DrawTextureParams {
-        dest_size: Some(vec2(PLAYER_RADIUS, PLAYER_RADIUS) * 2.0),
-        ..Default::default()
-    };
     let player_col = match state.player_state() {
         PlayerState::Walk => WHITE,
         PlayerState::Roll => WHITE,
         PlayerState::Recover => GOLD,
         PlayerState::Dead => MAROON,
     };
-    draw_texture_ex(
-        ass.player,
-        state.player_pos.x - PLAYER_RADIUS,
-        state.player_pos.y - PLAYER_RADIUS,
+    // face the way the knife is, and spin all the way round over the course of a roll
+    let facing_left = state.knife_dir.x < 0.0;
+    let player_spin = if state.player_state() == PlayerState::Roll {
+        let ticks = state.player_anim.ticks() as f32 + alpha;
+        let spin = ticks / state.tuning.player_roll_ticks as f32 * TAU;
+        if facing_left {
+            -spin
+        } else {
+            spin
+        }
+    } else {
+        0.0
+    };
+    anim::draw(
+        &ass.player,
+        &state.player_anim,
+        state.player_prev_pos.lerp(state.player_pos, alpha),
+        PLAYER_RADIUS,
+        player_spin,
+        facing_left,
         player_col,
-        player_params,
     );
 
+    // the knife's drawn pointing up. turn it to point along knife_dir, and flip it when pointing
+    // left so that the blade stays on the bottom rather than going upside down
     let knife_params = DrawTextureParams {
         dest_size: Some(vec2(KNIFE_RADIUS, KNIFE_RADIUS) * 2.0),
+        rotation: state.knife_dir.y.atan2(state.knife_dir.x) + FRAC_PI_2,
+        flip_x: facing_left,
         ..Default::default()
     };
+    let knife_pos = state.knife_prev_pos.lerp(state.knife_pos, alpha);
     draw_texture_ex(
         ass.player_weapon,
-        state.knife_pos.x - KNIFE_RADIUS,
-        state.knife_pos.y - KNIFE_RADIUS,
+        knife_pos.x - KNIFE_RADIUS,
+        knife_pos.y - KNIFE_RADIUS,
         WHITE,
         knife_params,
     );
 
-    let lem_params = DrawTextureParams {
-        dest_size: Some(vec2(LEMON_RADIUS, LEMON_RADIUS) * 2.0),
-        ..Default::default()
-    };
     for l in &state.lemons {
-        draw_texture_ex(
-            ass.lemon,
-            l.pos.x - LEMON_RADIUS,
-            l.pos.y - LEMON_RADIUS,
-            WHITE,
-            lem_params.clone(),
-        );
+        // limes ripen in to lemons just before attacking, by fading the lemon in over the top
+        let ripeness = l.ripeness(&state.tuning);
+        let tint = l.status.tint(state.tick);
+        let pos = l.prev_pos.lerp(l.pos, alpha);
+        if ripeness < 1.0 {
+            anim::draw(&ass.lime, &l.anim, pos, LEMON_RADIUS, 0.0, false, tint);
+        }
+        if ripeness > 0.0 {
+            let tint = Color::new(tint.r, tint.g, tint.b, ripeness);
+            anim::draw(&ass.lemon, &l.anim, pos, LEMON_RADIUS, 0.0, false, tint);
+        }
     }
 
-    let bread_params = DrawTextureParams {
-        dest_size: Some(vec2(BREAD_RADIUS, BREAD_RADIUS) * 2.0),
-        ..Default::default()
-    };
     for b in &state.breads {
-        draw_texture_ex(
-            ass.bread,
-            b.pos.x - BREAD_RADIUS,
-            b.pos.y - BREAD_RADIUS,
-            WHITE,
-            bread_params.clone(),
-        );
+        // telegraph the charge. shake harder and draw a firmer line the closer it is to going
+        let wind_up = b.wind_up(&state.tuning);
+        let mut pos = b.prev_pos.lerp(b.pos, alpha);
+        if wind_up > 0.0 {
+            let line_col = Color::new(1.0, 0.2, 0.1, 0.15 + wind_up * 0.5);
+            draw_line(
+                pos.x,
+                pos.y,
+                b.attacking.x,
+                b.attacking.y,
+                2.0 + wind_up * 4.0,
+                line_col,
+            );
+
+            let t = state.tick as f32;
+            pos += vec2((t * 1.7).sin(), (t * 2.3).cos()) * wind_up * BREAD_SHAKE;
+        }
+
+        let tint = b.status.tint(state.tick);
+        anim::draw(&ass.bread, &b.anim, pos, BREAD_RADIUS, 0.0, false, tint);
     }
 
-    let grape_params = DrawTextureParams {
-        dest_size: Some(vec2(GRAPE_RADIUS, GRAPE_RADIUS) * 2.0),
-        ..Default::default()
-    };
     for g in &state.grapes {
-        draw_texture_ex(
-            ass.grape,
-            g.pos.x - GRAPE_RADIUS,
-            g.pos.y - GRAPE_RADIUS,
-            WHITE,
-            grape_params.clone(),
-        );
+        let tint = g.status.tint(state.tick);
+        let pos = g.prev_pos.lerp(g.pos, alpha);
+        anim::draw(&ass.grape, &g.anim, pos, GRAPE_RADIUS, 0.0, false, tint);
     }
 
     let bull_params = DrawTextureParams {
@@ -649,18 +1598,31 @@ fn render(state: &GameState, ass: &Assets) {
     let bull_colour = Color::new(0.7 + t, 0.7 + t, 0.7 + t, 1.);
 
     for b in &state.bullets {
+        let pos = b.prev_pos.lerp(b.pos, alpha);
         draw_texture_ex(
             ass.bullet,
-            b.pos.x - BULLET_RADIUS,
-            b.pos.y - BULLET_RADIUS,
+            pos.x - BULLET_RADIUS,
+            pos.y - BULLET_RADIUS,
             bull_colour,
             bull_params.clone(),
         );
     }
 
-    if state.game_over {
-        let score_text = format!("You lasted {0} seconds", state.tick / TICKS_PER_SEC);
-        draw_text("Game over. Press R to restart", 300.0, 300.0, 30.0, WHITE);
-        draw_text(&score_text, 400.0, 330.0, 30.0, WHITE);
+    overlay.draw_world(state, alpha);
+
+    // point at anything that's off screen, so players don't get blindsided in big arenas
+    let view = cam.view_rect();
+    for l in &state.lemons {
+        camera::draw_edge_marker(view, l.pos, YELLOW);
+    }
+    for g in &state.grapes {
+        camera::draw_edge_marker(view, g.pos, PURPLE);
+    }
+    for b in &state.breads {
+        camera::draw_edge_marker(view, b.pos, ORANGE);
     }
+
+    macroquad::camera::set_camera(&camera::hud_camera());
+    hud::draw(state, ass.font);
+    overlay.draw_hud(state, ass.font);
 }
@@ -0,0 +1,157 @@
+// debug overlay, toggled with F3. draws what the game's thinking over the top of it: what everything
+// collides as, where enemies are going, and some numbers under the score.
+use crate::hud::{Anchor, Layout};
+use crate::{
+    GameState, BREAD_RADIUS, BULLET_RADIUS, GRAPE_RADIUS, KNIFE_RADIUS, LEMON_RADIUS,
+    PLAYER_RADIUS, TICKS_PER_SEC,
+};
+use macroquad::prelude::*;
+
+const TOGGLE: KeyCode = KeyCode::F3;
+const LINE: f32 = 1.5;
+const TIMER_SIZE: f32 = 16.0;
+// room for the time, score and wave, which the numbers go under
+const BELOW_PROGRESS: f32 = 110.0;
+
+pub struct Overlay {
+    visible: bool,
+    // ticks so far this second, and how many there were in the last whole one
+    ticks: i32,
+    counting_since: f64,
+    tick_rate: i32,
+}
+
+impl Overlay {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            ticks: 0,
+            counting_since: get_time(),
+            tick_rate: 0,
+        }
+    }
+
+    // call every frame
+    pub fn update(&mut self) {
+        if is_key_pressed(TOGGLE) {
+            self.visible = !self.visible;
+        }
+        if get_time() - self.counting_since >= 1.0 {
+            self.tick_rate = self.ticks;
+            self.ticks = 0;
+            self.counting_since = get_time();
+        }
+    }
+
+    // call every tick
+    pub fn ticked(&mut self) {
+        self.ticks += 1;
+    }
+
+    // collision circles and where things are headed. needs the world camera to be active
+    pub fn draw_world(&self, state: &GameState, alpha: f32) {
+        if !self.visible {
+            return;
+        }
+
+        let player_pos = state.player_prev_pos.lerp(state.player_pos, alpha);
+        circle(player_pos, PLAYER_RADIUS, WHITE);
+        let knife_pos = state.knife_prev_pos.lerp(state.knife_pos, alpha);
+        circle(knife_pos, KNIFE_RADIUS, SKYBLUE);
+
+        for l in &state.lemons {
+            let pos = l.prev_pos.lerp(l.pos, alpha);
+            circle(pos, LEMON_RADIUS, YELLOW);
+            // attacking lemons go for the player, anything else is wandering
+            if !l.is_attacking() {
+                line(pos, l.wander_to, Color::new(1.0, 1.0, 0.0, 0.4));
+                cross(l.wander_to, YELLOW);
+            }
+        }
+
+        for b in &state.breads {
+            let pos = b.prev_pos.lerp(b.pos, alpha);
+            circle(pos, BREAD_RADIUS, ORANGE);
+            if b.is_charging() || b.wind_up(&state.tuning) > 0.0 {
+                line(pos, b.attacking, Color::new(1.0, 0.6, 0.0, 0.4));
+                circle(b.attacking, BREAD_RADIUS, Color::new(1.0, 0.6, 0.0, 0.4));
+            }
+        }
+
+        for g in &state.grapes {
+            let pos = g.prev_pos.lerp(g.pos, alpha);
+            circle(pos, GRAPE_RADIUS, PURPLE);
+            // how long until the next volley, or the shots left in this one
+            let timer = if g.shots_left > 0 {
+                format!("{} left", g.shots_left)
+            } else {
+                format!("{:.1}s", g.attacks_in as f32 / TICKS_PER_SEC as f32)
+            };
+            let width = measure_text(&timer, None, TIMER_SIZE as u16, 1.0).width;
+            let at = pos - vec2(width / 2.0, GRAPE_RADIUS + 4.0);
+            draw_text(&timer, at.x, at.y, TIMER_SIZE, WHITE);
+        }
+
+        for b in &state.bullets {
+            circle(b.prev_pos.lerp(b.pos, alpha), BULLET_RADIUS, RED);
+        }
+    }
+
+    // tick rate, how many of everything there is, and the wave. needs the hud camera to be active
+    pub fn draw_hud(&self, state: &GameState, font: Font) {
+        if !self.visible {
+            return;
+        }
+
+        let mut numbers = Layout::new(font, Anchor::TopRight);
+        numbers.gap(BELOW_PROGRESS);
+        let fps = get_fps();
+        numbers.text(
+            &format!("{} ticks/s, {} fps", self.tick_rate, fps),
+            16.0,
+            GREEN,
+        );
+        numbers.text(
+            &format!(
+                "{} lemons, {} grapes, {} breads",
+                state.lemons.len(),
+                state.grapes.len(),
+                state.breads.len()
+            ),
+            16.0,
+            GREEN,
+        );
+        numbers.text(
+            &format!(
+                "{} bullets, {} corpses",
+                state.bullets.len(),
+                state.corpses.len()
+            ),
+            16.0,
+            GREEN,
+        );
+        let next_in = (state.next_wave_at_tick - state.tick) as f32 / TICKS_PER_SEC as f32;
+        numbers.text(
+            &format!(
+                "wave {}, next in {:.1}s",
+                state.next_wave_num,
+                next_in.max(0.0)
+            ),
+            16.0,
+            GREEN,
+        );
+    }
+}
+
+fn circle(pos: Vec2, radius: f32, colour: Color) {
+    draw_circle_lines(pos.x, pos.y, radius, LINE, colour);
+}
+
+fn line(from: Vec2, to: Vec2, colour: Color) {
+    draw_line(from.x, from.y, to.x, to.y, LINE, colour);
+}
+
+fn cross(pos: Vec2, colour: Color) {
+    line(pos - vec2(4.0, 4.0), pos + vec2(4.0, 4.0), colour);
+    line(pos - vec2(4.0, -4.0), pos + vec2(4.0, -4.0), colour);
+}
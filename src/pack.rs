@@ -0,0 +1,186 @@
+// asset packs, for reskinning the game without rebuilding it. a pack is a directory, or a zip file,
+// with files named the same as the ones built in to the game (see assets.rs). files in a zip can be
+// in folders, only their names matter. anything a pack doesn't have comes from the built in assets.
+use std::collections::HashMap;
+
+const END_OF_DIRECTORY: u32 = 0x0605_4b50;
+const DIRECTORY_ENTRY: u32 = 0x0201_4b50;
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+
+pub enum Pack {
+    Dir(String),
+    // everything in the zip, by file name, already decompressed
+    Zip(HashMap<String, Vec<u8>>),
+}
+
+// paths ending in .zip are read as zip files, anything else as a directory
+pub async fn open(path: &str) -> Result<Pack, String> {
+    if !path.ends_with(".zip") {
+        return Ok(Pack::Dir(path.to_string()));
+    }
+    let bytes = macroquad::file::load_file(path)
+        .await
+        .map_err(|e| e.to_string())?;
+    let files = read_zip(&bytes).map_err(|e| format!("{}: {}", path, e))?;
+    Ok(Pack::Zip(files))
+}
+
+impl Pack {
+    // the contents of the file called `name`, if the pack has one
+    pub async fn get(&self, name: &str) -> Option<Vec<u8>> {
+        match self {
+            Pack::Dir(dir) => {
+                let path = std::path::Path::new(dir).join(name);
+                macroquad::file::load_file(&path.to_string_lossy())
+                    .await
+                    .ok()
+            }
+            Pack::Zip(files) => files.get(name).cloned(),
+        }
+    }
+}
+
+// only stored and deflated files are supported, which is all anything makes by default
+fn read_zip(bytes: &[u8]) -> Result<HashMap<String, Vec<u8>>, String> {
+    // the end of directory record is at the end, but there can be a comment after it
+    let end = (0..bytes.len().saturating_sub(21))
+        .rev()
+        .find(|&i| read_u32(bytes, i) == Some(END_OF_DIRECTORY))
+        .ok_or("not a zip file")?;
+    let count = read_u16(bytes, end + 10).ok_or("not a zip file")?;
+    let mut at = read_u32(bytes, end + 16).ok_or("not a zip file")? as usize;
+
+    let mut files = HashMap::new();
+    for _ in 0..count {
+        let corrupt = || "the zip file is corrupt".to_string();
+        if read_u32(bytes, at) != Some(DIRECTORY_ENTRY) {
+            return Err(corrupt());
+        }
+        let method = read_u16(bytes, at + 10).ok_or_else(corrupt)?;
+        let compressed_size = read_u32(bytes, at + 20).ok_or_else(corrupt)? as usize;
+        let name_len = read_u16(bytes, at + 28).ok_or_else(corrupt)? as usize;
+        let extra_len = read_u16(bytes, at + 30).ok_or_else(corrupt)? as usize;
+        let comment_len = read_u16(bytes, at + 32).ok_or_else(corrupt)? as usize;
+        let local = read_u32(bytes, at + 42).ok_or_else(corrupt)? as usize;
+        let path = bytes.get(at + 46..at + 46 + name_len).ok_or_else(corrupt)?;
+        let path = String::from_utf8_lossy(path).to_string();
+        at += 46 + name_len + extra_len + comment_len;
+
+        // folders have entries of their own
+        if path.ends_with('/') {
+            continue;
+        }
+        let name = path.rsplit('/').next().unwrap_or(&path).to_string();
+
+        // the local header's extra field can be a different length to the directory's one
+        if read_u32(bytes, local) != Some(LOCAL_HEADER) {
+            return Err(corrupt());
+        }
+        let local_name_len = read_u16(bytes, local + 26).ok_or_else(corrupt)? as usize;
+        let local_extra_len = read_u16(bytes, local + 28).ok_or_else(corrupt)? as usize;
+        let start = local + 30 + local_name_len + local_extra_len;
+        let data = bytes
+            .get(start..start + compressed_size)
+            .ok_or_else(corrupt)?;
+
+        let data = match method {
+            0 => data.to_vec(),
+            8 => miniz_oxide::inflate::decompress_to_vec(data)
+                .map_err(|_| format!("{} is corrupt", path))?,
+            _ => {
+                return Err(format!(
+                "{} is compressed in a way that isn't supported. use deflate, or no compression",
+                path
+            ))
+            }
+        };
+        files.insert(name, data);
+    }
+    Ok(files)
+}
+
+fn read_u16(bytes: &[u8], at: usize) -> Option<u16> {
+    let b = bytes.get(at..at + 2)?;
+    Some(u16::from_le_bytes([b[0], b[1]]))
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> Option<u32> {
+    let b = bytes.get(at..at + 4)?;
+    Some(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a zip of (path, compression method, data as it's stored) entries
+    fn zip(entries: &[(&str, u16, &[u8])]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut directory = Vec::new();
+        for &(path, method, data) in entries {
+            let local = bytes.len() as u32;
+            bytes.extend(LOCAL_HEADER.to_le_bytes());
+            bytes.extend([0; 4]);
+            bytes.extend(method.to_le_bytes());
+            bytes.extend([0; 12]);
+            bytes.extend((data.len() as u32).to_le_bytes());
+            bytes.extend((path.len() as u16).to_le_bytes());
+            bytes.extend([0; 2]);
+            bytes.extend(path.as_bytes());
+            bytes.extend(data);
+
+            directory.extend(DIRECTORY_ENTRY.to_le_bytes());
+            directory.extend([0; 6]);
+            directory.extend(method.to_le_bytes());
+            directory.extend([0; 8]);
+            directory.extend((data.len() as u32).to_le_bytes());
+            directory.extend([0; 4]);
+            directory.extend((path.len() as u16).to_le_bytes());
+            directory.extend([0; 12]);
+            directory.extend(local.to_le_bytes());
+            directory.extend(path.as_bytes());
+        }
+        let start = bytes.len() as u32;
+        let size = directory.len() as u32;
+        bytes.extend(directory);
+        bytes.extend(END_OF_DIRECTORY.to_le_bytes());
+        bytes.extend([0; 6]);
+        bytes.extend((entries.len() as u16).to_le_bytes());
+        bytes.extend(size.to_le_bytes());
+        bytes.extend(start.to_le_bytes());
+        bytes.extend([0; 2]);
+        bytes
+    }
+
+    #[test]
+    fn reads_stored_files() {
+        let files = read_zip(&zip(&[("pack/", 0, b""), ("pack/font.ttf", 0, b"stored")])).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files["font.ttf"], b"stored");
+    }
+
+    #[test]
+    fn reads_deflated_files() {
+        let text = b"deflated deflated deflated deflated".to_vec();
+        let deflated = miniz_oxide::deflate::compress_to_vec(&text, 6);
+        let files = read_zip(&zip(&[("waves.txt", 8, &deflated)])).unwrap();
+        assert_eq!(files["waves.txt"], text);
+    }
+
+    #[test]
+    fn rejects_truncated_files() {
+        let bytes = zip(&[("waves.txt", 0, b"stored")]);
+        for len in [0, 10, 30, bytes.len() - 1] {
+            assert!(read_zip(&bytes[..len]).is_err());
+        }
+    }
+
+    #[test]
+    fn rejects_unsupported_compression() {
+        // 12 is bzip2
+        let err = read_zip(&zip(&[("waves.txt", 12, b"bzip2")]))
+            .err()
+            .unwrap();
+        assert!(err.contains("isn't supported"), "{}", err);
+    }
+}
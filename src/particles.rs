@@ -0,0 +1,152 @@
+// purely cosmetic particles: juice when things get diced, dust behind a roll, puffs when grapes
+// shoot. these have their own random numbers and nothing in the game state ever reads them, so they
+// can't change how a game plays out.
+use macroquad::prelude::*;
+
+// oldest particles get dropped past this, so swarm mode can't run away with them
+const PARTICLES_MAX: usize = 4096;
+
+const JUICE_COUNT: usize = 14;
+const JUICE_SPEED: f32 = 5.0;
+const JUICE_TICKS: i32 = 40;
+const DUST_TICKS: i32 = 25;
+const PUFF_COUNT: usize = 4;
+const PUFF_TICKS: i32 = 15;
+
+struct Particle {
+    pos: Vec2,
+    vel: Vec2,
+    // fraction of velocity kept each tick
+    drag: f32,
+    size: f32,
+    // size at the end of its life, as a fraction of `size`
+    end_size: f32,
+    colour: Color,
+    age: i32,
+    lifetime: i32,
+}
+
+pub struct Particles {
+    particles: Vec<Particle>,
+    rng: Rng,
+}
+
+impl Particles {
+    pub fn new() -> Self {
+        Self {
+            particles: Vec::new(),
+            rng: Rng::new(0x5eed_f00d),
+        }
+    }
+
+    pub fn tick(&mut self) {
+        for p in &mut self.particles {
+            p.pos += p.vel;
+            p.vel *= p.drag;
+            p.age += 1;
+        }
+        self.particles.retain(|p| p.age < p.lifetime);
+
+        if self.particles.len() > PARTICLES_MAX {
+            let excess = self.particles.len() - PARTICLES_MAX;
+            self.particles.drain(..excess);
+        }
+    }
+
+    // a splat of juice flying out from something that's just been killed
+    pub fn juice(&mut self, pos: Vec2, colour: Color) {
+        for _ in 0..JUICE_COUNT {
+            let vel = self.rng.dir() * self.rng.range(0.3, 1.0) * JUICE_SPEED;
+            let size = self.rng.range(3.0, 7.0);
+            let lifetime = (JUICE_TICKS as f32 * self.rng.range(0.6, 1.0)) as i32;
+            self.particles.push(Particle {
+                pos,
+                vel,
+                drag: 0.88,
+                size,
+                end_size: 0.6,
+                colour,
+                age: 0,
+                lifetime,
+            });
+        }
+    }
+
+    // kicked up behind something moving along the floor at `vel`
+    pub fn dust(&mut self, pos: Vec2, vel: Vec2) {
+        let scatter = self.rng.dir() * self.rng.range(4.0, 12.0);
+        let grey = self.rng.range(0.7, 0.85);
+        self.particles.push(Particle {
+            pos: pos + scatter,
+            vel: -vel * 0.15 + self.rng.dir() * 0.3,
+            drag: 0.92,
+            size: self.rng.range(4.0, 7.0),
+            end_size: 2.0,
+            colour: Color::new(grey, grey * 0.95, grey * 0.85, 0.5),
+            age: 0,
+            lifetime: DUST_TICKS,
+        });
+    }
+
+    // a little puff of smoke where a shot went off, heading `dir`
+    pub fn puff(&mut self, pos: Vec2, dir: Vec2) {
+        for _ in 0..PUFF_COUNT {
+            let spread = self.rng.dir() * 0.6;
+            self.particles.push(Particle {
+                pos,
+                vel: (dir.normalize_or_zero() + spread) * self.rng.range(1.0, 2.5),
+                drag: 0.85,
+                size: self.rng.range(3.0, 5.0),
+                end_size: 2.5,
+                colour: Color::new(0.95, 0.9, 1.0, 0.6),
+                age: 0,
+                lifetime: PUFF_TICKS,
+            });
+        }
+    }
+
+    // needs the world camera to be active. anything outside `view` is skipped. `alpha` is how far
+    // through to the next tick to draw them
+    pub fn render(&self, view: Rect, alpha: f32) {
+        for p in &self.particles {
+            let t = (p.age as f32 + alpha) / p.lifetime as f32;
+            let size = p.size * (1.0 + (p.end_size - 1.0) * t);
+            let pos = p.pos + p.vel * alpha;
+            let bounds = Rect::new(pos.x - size, pos.y - size, size * 2.0, size * 2.0);
+            if !view.overlaps(&bounds) {
+                continue;
+            }
+            let mut colour = p.colour;
+            colour.a *= 1.0 - t;
+            draw_circle(pos.x, pos.y, size, colour);
+        }
+    }
+}
+
+// xorshift. macroquad's random numbers drive the game, so particles mustn't take any from it
+struct Rng(u32);
+
+impl Rng {
+    fn new(seed: u32) -> Self {
+        Self(seed.max(1))
+    }
+
+    fn next(&mut self) -> u32 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.0 = x;
+        x
+    }
+
+    fn range(&mut self, low: f32, high: f32) -> f32 {
+        let t = (self.next() >> 8) as f32 / (1u32 << 24) as f32;
+        low + (high - low) * t
+    }
+
+    fn dir(&mut self) -> Vec2 {
+        let angle = self.range(0.0, std::f32::consts::TAU);
+        vec2(angle.cos(), angle.sin())
+    }
+}
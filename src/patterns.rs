@@ -0,0 +1,154 @@
+// bullet patterns for grapes. waves refer to these by name, so new combinations of the shapes below
+// only need a new table entry rather than new code.
+use macroquad::prelude::*;
+use macroquad::rand::gen_range;
+
+// inaccuracy when shooting aimed bullets, in pixels around the target
+const AIM_RNG: f32 = 60.0;
+
+#[derive(Copy, Clone)]
+pub enum Shape {
+    // single bullet at the target, with some random inaccuracy
+    Aimed,
+    // `count` bullets spread evenly over `spread` radians, centred on the target
+    Fan { count: u8, spread: f32 },
+    // `count` bullets evenly spaced around a full circle, starting at the target
+    Ring { count: u8 },
+    // single bullet per shot, each shot rotating `step` radians further around the grape. ignores
+    // the target, so it sweeps the whole arena
+    Spiral { step: f32 },
+}
+
+#[derive(Copy, Clone)]
+pub struct Pattern {
+    pub shape: Shape,
+    // how many times the shape is fired per volley, and how many ticks apart
+    pub shots: u8,
+    pub shot_gap: i32,
+    // if non-zero, bullets turn to face the player again this many ticks after being fired
+    pub home_after: i32,
+}
+
+const AIMED: Pattern = Pattern {
+    shape: Shape::Aimed,
+    shots: 1,
+    shot_gap: 0,
+    home_after: 0,
+};
+
+static PATTERNS: [(&str, Pattern); 7] = [
+    ("aimed", AIMED),
+    (
+        "fan3",
+        Pattern {
+            shape: Shape::Fan {
+                count: 3,
+                spread: 0.6,
+            },
+            ..AIMED
+        },
+    ),
+    (
+        "fan5",
+        Pattern {
+            shape: Shape::Fan {
+                count: 5,
+                spread: 1.2,
+            },
+            ..AIMED
+        },
+    ),
+    (
+        "ring",
+        Pattern {
+            shape: Shape::Ring { count: 10 },
+            ..AIMED
+        },
+    ),
+    (
+        "spiral",
+        Pattern {
+            shape: Shape::Spiral { step: 0.5 },
+            shots: 16,
+            shot_gap: 4,
+            home_after: 0,
+        },
+    ),
+    (
+        "burst",
+        Pattern {
+            shots: 3,
+            shot_gap: 8,
+            ..AIMED
+        },
+    ),
+    (
+        "homing",
+        Pattern {
+            shape: Shape::Ring { count: 6 },
+            shots: 1,
+            shot_gap: 0,
+            home_after: 40,
+        },
+    ),
+];
+
+// looks up a pattern by name. unknown names fall back to a single aimed bullet, so a typo in a wave
+// still gives a working grape.
+pub fn get(name: &str) -> Pattern {
+    PATTERNS
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, p)| *p)
+        .unwrap_or(AIMED)
+}
+
+// the table's own copy of a pattern's name, if there's a pattern called that
+pub fn name(name: &str) -> Option<&'static str> {
+    PATTERNS.iter().map(|(n, _)| *n).find(|n| *n == name)
+}
+
+impl Pattern {
+    // unit directions for shot number `shot` of a volley fired from `from` at `target`.
+    pub fn directions(&self, shot: u8, from: Vec2, target: Vec2, out: &mut Vec<Vec2>) {
+        let aim = (target - from).normalize_or_zero();
+        let aim = if aim == Vec2::ZERO {
+            vec2(1.0, 0.0)
+        } else {
+            aim
+        };
+
+        match self.shape {
+            Shape::Aimed => {
+                let target_off = vec2(gen_range(-AIM_RNG, AIM_RNG), gen_range(-AIM_RNG, AIM_RNG));
+                out.push((target + target_off - from).normalize_or_zero());
+            }
+            Shape::Fan { count, spread } => {
+                let count = count.max(1);
+                let gap = if count > 1 {
+                    spread / (count - 1) as f32
+                } else {
+                    0.0
+                };
+                let start = -spread / 2.0;
+                for i in 0..count {
+                    out.push(rotate(aim, start + gap * i as f32));
+                }
+            }
+            Shape::Ring { count } => {
+                let count = count.max(1);
+                let gap = std::f32::consts::TAU / count as f32;
+                for i in 0..count {
+                    out.push(rotate(aim, gap * i as f32));
+                }
+            }
+            Shape::Spiral { step } => {
+                out.push(rotate(vec2(1.0, 0.0), step * shot as f32));
+            }
+        }
+    }
+}
+
+fn rotate(v: Vec2, angle: f32) -> Vec2 {
+    Mat2::from_angle(angle) * v
+}
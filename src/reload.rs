@@ -0,0 +1,206 @@
+// debug builds only. watches asset files, the waves and the tuning, and reloads any that change while
+// the game's running, so they can all be tweaked without rebuilding. there's nothing to be told
+// about changes with, so this just checks modification times every so often.
+//
+// built in files are watched where they're built from, in src, so that needs to be in the working
+// directory. `cargo run` does that.
+use crate::anim::Sheet;
+use crate::assets::{self, Assets};
+use crate::audio::Mixer;
+use crate::level::Level;
+use crate::pack;
+use crate::settings::Settings;
+use crate::tuning::{self, Tuning};
+use crate::waves::{self, Waves};
+use macroquad::audio::{stop_sound, Sound};
+use macroquad::prelude::*;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+const SOURCE_DIR: &str = "src";
+const CHECK_EVERY: f64 = 0.5;
+// files are left alone until they've stopped changing for this long, so they aren't read half saved
+const SETTLE: Duration = Duration::from_millis(300);
+
+enum Source {
+    Asset(&'static str),
+    // a zip asset pack. everything in it gets reloaded
+    Zip(String),
+    Waves,
+    Tuning,
+}
+
+struct Watched {
+    path: PathBuf,
+    source: Source,
+    modified: Option<SystemTime>,
+}
+
+pub struct Watcher {
+    files: Vec<Watched>,
+    next_check: f64,
+}
+
+impl Watcher {
+    // `pack_path` and `tuning_path` are what the game was started with, if anything
+    pub fn new(pack_path: Option<&str>, tuning_path: Option<&str>) -> Self {
+        let mut files = Vec::new();
+        let mut watch = |path: PathBuf, source| {
+            let modified = modified(&path);
+            files.push(Watched {
+                path,
+                source,
+                modified,
+            });
+        };
+
+        match pack_path {
+            Some(zip) if zip.ends_with(".zip") => watch(zip.into(), Source::Zip(zip.to_string())),
+            dir => {
+                let dir = dir.unwrap_or(SOURCE_DIR);
+                for (name, _) in assets::BUILT_IN {
+                    watch(Path::new(dir).join(name), Source::Asset(name));
+                }
+            }
+        }
+        watch(Path::new(SOURCE_DIR).join("waves.txt"), Source::Waves);
+        if let Some(path) = tuning_path {
+            watch(path.into(), Source::Tuning);
+        }
+
+        Self {
+            files,
+            next_check: 0.0,
+        }
+    }
+
+    // call every frame. anything that can't be reloaded is left as it was, with the reason printed
+    pub async fn update(
+        &mut self,
+        ass: &mut Assets,
+        mixer: &mut Mixer,
+        settings: &Settings,
+        level: &mut Level,
+        waves: &mut Waves,
+        tuning: &mut Tuning,
+    ) {
+        if get_time() < self.next_check {
+            return;
+        }
+        self.next_check = get_time() + CHECK_EVERY;
+
+        for file in &mut self.files {
+            let modified = modified(&file.path);
+            let settled = modified
+                .and_then(|m| m.elapsed().ok())
+                .is_some_and(|age| age >= SETTLE);
+            if modified == file.modified || !settled {
+                continue;
+            }
+            file.modified = modified;
+
+            let result = match &file.source {
+                Source::Asset(name) => match std::fs::read(&file.path) {
+                    Ok(bytes) => reload_asset(ass, mixer, settings, level, name, &bytes).await,
+                    Err(e) => Err(e.to_string()),
+                },
+                Source::Zip(path) => reload_zip(ass, mixer, settings, level, path).await,
+                Source::Waves => std::fs::read_to_string(&file.path)
+                    .map_err(|e| e.to_string())
+                    .and_then(|text| waves::parse(&text))
+                    .map(|new| *waves = new),
+                Source::Tuning => std::fs::read_to_string(&file.path)
+                    .map_err(|e| e.to_string())
+                    .and_then(|text| tuning::parse(&text))
+                    .map(|new| *tuning = new),
+            };
+            match result {
+                Ok(()) => eprintln!("reloaded {}", file.path.display()),
+                Err(e) => eprintln!("couldn't reload {}: {}", file.path.display(), e),
+            }
+        }
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+async fn reload_zip(
+    ass: &mut Assets,
+    mixer: &mut Mixer,
+    settings: &Settings,
+    level: &mut Level,
+    path: &str,
+) -> Result<(), String> {
+    let pack = pack::open(path).await?;
+    for (name, _) in assets::BUILT_IN {
+        if let Some(bytes) = pack.get(name).await {
+            reload_asset(ass, mixer, settings, level, name, &bytes).await?;
+        }
+    }
+    Ok(())
+}
+
+// swaps the asset loaded from file `name` for one made from `bytes`. see assets::load for which file
+// is which
+async fn reload_asset(
+    ass: &mut Assets,
+    mixer: &mut Mixer,
+    settings: &Settings,
+    level: &mut Level,
+    name: &str,
+    bytes: &[u8],
+) -> Result<(), String> {
+    let sound = || assets::decode_sound(bytes);
+    let texture = || assets::decode_texture(bytes);
+
+    match name {
+        // music layers play in time with each other, so they all start again together
+        "radmusic.ogg" | "music_calm.ogg" | "heartbeat.ogg" => {
+            let new = sound().await?;
+            for old in [ass.bgm, ass.bgm_calm, ass.heartbeat] {
+                stop_sound(old);
+            }
+            let layer: &mut Sound = match name {
+                "radmusic.ogg" => &mut ass.bgm,
+                "music_calm.ogg" => &mut ass.bgm_calm,
+                _ => &mut ass.heartbeat,
+            };
+            *layer = new;
+            mixer.start_music(ass, settings);
+        }
+        "game_over.ogg" => ass.game_over = sound().await?,
+        "60013__qubodup__whoosh.ogg" => ass.roll = sound().await?,
+        "enemy_shoot_left.ogg" => ass.enemy_shoot.left = sound().await?,
+        "enemy_shoot_right.ogg" => ass.enemy_shoot.right = sound().await?,
+        "enemy_death_left.ogg" => ass.enemy_death.left = sound().await?,
+        "enemy_death_right.ogg" => ass.enemy_death.right = sound().await?,
+        "bread_attack_left.ogg" => ass.bread_attack.left = sound().await?,
+        "bread_attack_right.ogg" => ass.bread_attack.right = sound().await?,
+
+        // the default kitchen's tileset is the background, so that gets swapped too
+        "background.png" => {
+            let old = ass.background;
+            ass.background = texture()?;
+            for ts in level.tiles.iter_mut().flat_map(|t| t.tilesets.iter_mut()) {
+                if ts.texture == Some(old) {
+                    ts.texture = Some(ass.background);
+                }
+            }
+        }
+        "cook_sheet.png" => ass.player = Sheet::new(texture()?),
+        "playerweapon.png" => ass.player_weapon = texture()?,
+        "lime_sheet.png" => ass.lime = Sheet::new(texture()?),
+        "lemon_sheet.png" => ass.lemon = Sheet::new(texture()?),
+        "grape_sheet.png" => ass.grape = Sheet::new(texture()?),
+        "strawberry.png" => ass.bullet = texture()?,
+        "bread_sheet.png" => ass.bread = Sheet::new(texture()?),
+
+        "DejaVuSans-Bold.ttf" => {
+            ass.font = load_ttf_font_from_bytes(bytes).map_err(|e| e.to_string())?
+        }
+        _ => {}
+    }
+    Ok(())
+}
@@ -0,0 +1,142 @@
+// player settings, changed in a window that escape opens and closes. the game's paused while it's
+// open. settings are saved to a file whenever the window closes, and loaded again at startup.
+use macroquad::prelude::*;
+use macroquad::ui::{hash, root_ui};
+
+// in the working directory. the web version can't get at files, so never has any saved settings
+const SETTINGS_PATH: &str = "settings.txt";
+
+pub struct Settings {
+    pub open: bool,
+
+    // how strong each camera effect is, from 0 (off) to 1, for anyone that finds them too much
+    pub shake: f32,
+    pub hit_stop: f32,
+    pub death_zoom: f32,
+
+    // volumes, from 0 to 1. music and sound effects are both turned down by the master volume too
+    pub master: f32,
+    pub music: f32,
+    pub sfx: f32,
+    pub muted: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            open: false,
+            shake: 1.0,
+            hit_stop: 1.0,
+            death_zoom: 1.0,
+            master: 1.0,
+            music: 1.0,
+            sfx: 1.0,
+            muted: false,
+        }
+    }
+}
+
+impl Settings {
+    // saved settings, or the defaults for anything that wasn't saved
+    pub fn load() -> Self {
+        let mut settings = Self::default();
+        let Ok(text) = std::fs::read_to_string(SETTINGS_PATH) else {
+            return settings;
+        };
+
+        // one `name value` per line. anything that doesn't make sense is left at the default
+        for line in text.lines() {
+            let mut words = line.split_whitespace();
+            let (Some(name), Some(value)) = (words.next(), words.next()) else {
+                continue;
+            };
+            if name == "muted" {
+                settings.muted = value == "true";
+                continue;
+            }
+            let Some(setting) = settings.slider_mut(name) else {
+                continue;
+            };
+            if let Ok(value) = value.parse::<f32>() {
+                *setting = value.clamp(0.0, 1.0);
+            }
+        }
+        settings
+    }
+
+    // nothing to be done if it can't be saved, it just won't be remembered next time
+    fn save(&self) {
+        let text = format!(
+            "shake {}\nhit_stop {}\ndeath_zoom {}\nmaster {}\nmusic {}\nsfx {}\nmuted {}\n",
+            self.shake,
+            self.hit_stop,
+            self.death_zoom,
+            self.master,
+            self.music,
+            self.sfx,
+            self.muted
+        );
+        let _ = std::fs::write(SETTINGS_PATH, text);
+    }
+
+    fn slider_mut(&mut self, name: &str) -> Option<&mut f32> {
+        match name {
+            "shake" => Some(&mut self.shake),
+            "hit_stop" => Some(&mut self.hit_stop),
+            "death_zoom" => Some(&mut self.death_zoom),
+            "master" => Some(&mut self.master),
+            "music" => Some(&mut self.music),
+            "sfx" => Some(&mut self.sfx),
+            _ => None,
+        }
+    }
+
+    // what the music and sound effects should actually play at, after the master volume and mute
+    pub fn music_volume(&self) -> f32 {
+        self.bus_volume(self.music)
+    }
+
+    pub fn sfx_volume(&self) -> f32 {
+        self.bus_volume(self.sfx)
+    }
+
+    fn bus_volume(&self, bus: f32) -> f32 {
+        if self.muted {
+            0.0
+        } else {
+            self.master * bus
+        }
+    }
+
+    // call every frame. ui is drawn over everything else, whenever it's called
+    pub fn update(&mut self) {
+        if is_key_pressed(KeyCode::M) {
+            self.muted = !self.muted;
+            self.save();
+        }
+        if is_key_pressed(KeyCode::Escape) {
+            self.open = !self.open;
+            if !self.open {
+                self.save();
+            }
+        }
+        if !self.open {
+            return;
+        }
+
+        let size = vec2(360.0, 260.0);
+        let pos = vec2(screen_width() - size.x, screen_height() - size.y) / 2.0;
+        root_ui().window(hash!(), pos, size, |ui| {
+            ui.label(None, "Paused. Escape to carry on");
+            ui.separator();
+            ui.slider(hash!(), "Screen shake", 0.0..1.0, &mut self.shake);
+            ui.slider(hash!(), "Hit-stop", 0.0..1.0, &mut self.hit_stop);
+            ui.slider(hash!(), "Death zoom", 0.0..1.0, &mut self.death_zoom);
+            ui.separator();
+            ui.slider(hash!(), "Master volume", 0.0..1.0, &mut self.master);
+            ui.slider(hash!(), "Music", 0.0..1.0, &mut self.music);
+            ui.slider(hash!(), "Sound effects", 0.0..1.0, &mut self.sfx);
+            ui.checkbox(hash!(), "Mute (M)", &mut self.muted);
+        });
+    }
+}
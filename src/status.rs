@@ -0,0 +1,162 @@
+// timed status effects on enemies, applied by hazards, power-ups and knife upgrades.
+use macroquad::prelude::*;
+
+// only burning is applied in the base game so far, by stoves
+#[allow(dead_code)]
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum Effect {
+    // moves at SLOWED_SPEED, and grapes shoot slower bullets
+    Slowed,
+    // can't move or shoot, but keeps counting down to its next attack
+    Stunned,
+    // completely stopped, timers included
+    Frozen,
+    // panics and runs faster, then dies when the burn runs out
+    Burning,
+}
+
+const SLOWED_SPEED: f32 = 0.5;
+const BURNING_SPEED: f32 = 1.5;
+
+// ticks left on each effect. 0 if not affected
+#[derive(Default)]
+pub struct Status {
+    slowed: i32,
+    stunned: i32,
+    frozen: i32,
+    burning: i32,
+}
+
+impl Status {
+    // applies an effect for `ticks`. won't shorten an effect that's already going to last longer.
+    pub fn apply(&mut self, effect: Effect, ticks: i32) {
+        let left = match effect {
+            Effect::Slowed => &mut self.slowed,
+            Effect::Stunned => &mut self.stunned,
+            Effect::Frozen => &mut self.frozen,
+            Effect::Burning => &mut self.burning,
+        };
+        *left = (*left).max(ticks);
+    }
+
+    // counts down all effects. returns true if the enemy burnt up this tick. freezing stops the
+    // burn counting down too, until it thaws
+    pub fn tick(&mut self) -> bool {
+        let frozen = self.is_frozen();
+        for left in [&mut self.slowed, &mut self.stunned, &mut self.frozen] {
+            *left = (*left - 1).max(0);
+        }
+
+        if self.burning > 0 && !frozen {
+            self.burning -= 1;
+            return self.burning == 0;
+        }
+        false
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.frozen > 0
+    }
+
+    // whether the enemy can attack this tick
+    pub fn can_attack(&self) -> bool {
+        self.frozen == 0 && self.stunned == 0
+    }
+
+    pub fn speed_mul(&self) -> f32 {
+        if !self.can_attack() {
+            return 0.0;
+        }
+
+        let mut mul = 1.0;
+        if self.slowed > 0 {
+            mul *= SLOWED_SPEED;
+        }
+        if self.burning > 0 {
+            mul *= BURNING_SPEED;
+        }
+        mul
+    }
+
+    // colour to draw the enemy with. the most severe effect wins
+    pub fn tint(&self, tick: i32) -> Color {
+        if self.frozen > 0 {
+            Color::new(0.6, 0.8, 1.0, 1.0)
+        } else if self.burning > 0 {
+            // flicker between orange and red
+            let t = (tick as f32 * 0.5).sin() * 0.5 + 0.5;
+            Color::new(1.0, 0.3 + t * 0.3, 0.1, 1.0)
+        } else if self.stunned > 0 {
+            Color::new(1.0, 1.0, 0.5, 1.0)
+        } else if self.slowed > 0 {
+            Color::new(0.7, 0.6, 1.0, 1.0)
+        } else {
+            WHITE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apply_keeps_the_longer_effect() {
+        let mut s = Status::default();
+        s.apply(Effect::Slowed, 10);
+        s.apply(Effect::Slowed, 5);
+        assert_eq!(s.slowed, 10);
+        s.apply(Effect::Slowed, 20);
+        assert_eq!(s.slowed, 20);
+        assert_eq!(s.stunned, 0);
+    }
+
+    #[test]
+    fn tick_counts_down_and_burns_up() {
+        let mut s = Status::default();
+        s.apply(Effect::Stunned, 1);
+        s.apply(Effect::Burning, 2);
+        assert!(!s.tick());
+        assert_eq!(s.stunned, 0);
+        assert!(s.tick());
+        assert!(!s.tick());
+        assert_eq!(s.burning, 0);
+    }
+
+    #[test]
+    fn freezing_holds_the_burn() {
+        let mut s = Status::default();
+        s.apply(Effect::Burning, 1);
+        s.apply(Effect::Frozen, 2);
+        assert!(!s.tick());
+        assert!(!s.tick());
+        assert!(!s.is_frozen());
+        assert!(s.tick());
+    }
+
+    #[test]
+    fn stuns_and_freezes_stop_attacks_and_movement() {
+        let mut s = Status::default();
+        assert!(s.can_attack());
+        assert_eq!(s.speed_mul(), 1.0);
+
+        s.apply(Effect::Stunned, 1);
+        assert!(!s.can_attack());
+        assert_eq!(s.speed_mul(), 0.0);
+        s.tick();
+
+        s.apply(Effect::Frozen, 1);
+        assert!(!s.can_attack());
+        assert_eq!(s.speed_mul(), 0.0);
+    }
+
+    #[test]
+    fn speed_mul_stacks_slowed_and_burning() {
+        let mut s = Status::default();
+        s.apply(Effect::Slowed, 5);
+        assert_eq!(s.speed_mul(), SLOWED_SPEED);
+        s.apply(Effect::Burning, 5);
+        assert_eq!(s.speed_mul(), SLOWED_SPEED * BURNING_SPEED);
+        assert!(s.can_attack());
+    }
+}
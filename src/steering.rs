@@ -0,0 +1,96 @@
+// steering behaviours for enemies, so crowds spread out and come at the player from different sides
+// rather than all stacking up on the same spot.
+use macroquad::prelude::*;
+use macroquad::rand::gen_range;
+use std::f32::consts::TAU;
+
+// which behaviours an enemy type uses, from its tuning. the weights scale how hard each behaviour
+// pushes relative to the enemy's own desired direction; 0 turns a behaviour off.
+#[derive(Copy, Clone)]
+pub struct Steering {
+    pub separation: f32,
+    pub avoidance: f32,
+    // approach the target at an angle, rather than straight on
+    pub flank: bool,
+}
+
+impl Steering {
+    // combined push from separation and obstacle avoidance, for something at `pos` with the given
+    // collision radius that wants to move along `heading`.
+    pub fn push(
+        &self,
+        pos: Vec2,
+        radius: f32,
+        heading: Vec2,
+        neighbours: &[Vec2],
+        obstacles: &[(Vec2, f32)],
+    ) -> Vec2 {
+        let mut push = Vec2::ZERO;
+        if self.separation > 0.0 {
+            push += separation(pos, radius * 3.0, neighbours) * self.separation;
+        }
+        if self.avoidance > 0.0 {
+            push += avoidance(pos, radius, heading, obstacles) * self.avoidance;
+        }
+        push
+    }
+}
+
+// push away from any neighbours closer than `range`, harder the closer they are. `neighbours`
+// shouldn't include ourselves. ones sitting exactly on `pos` push as hard as possible, in a random
+// direction, since there's no way to tell which way is away from them
+pub fn separation(pos: Vec2, range: f32, neighbours: &[Vec2]) -> Vec2 {
+    let mut push = Vec2::ZERO;
+    for n in neighbours {
+        let off = pos - *n;
+        let dist = off.length();
+        if dist == 0.0 {
+            let angle = gen_range(0.0, TAU);
+            push += vec2(angle.cos(), angle.sin());
+        } else if dist < range {
+            push += off / dist * (1.0 - dist / range);
+        }
+    }
+    push
+}
+
+// how far ahead to look for obstacles, in multiples of our own radius
+const LOOK_AHEAD: f32 = 4.0;
+
+// push sideways around any circular obstacle in the way of `heading`.
+pub fn avoidance(pos: Vec2, radius: f32, heading: Vec2, obstacles: &[(Vec2, f32)]) -> Vec2 {
+    let look = radius * LOOK_AHEAD;
+    let mut push = Vec2::ZERO;
+    for (centre, obs_radius) in obstacles {
+        let to = *centre - pos;
+        let ahead = to.dot(heading);
+        let reach = look + obs_radius;
+        if ahead <= 0.0 || ahead > reach {
+            continue;
+        }
+
+        // closest point on our path to the obstacle's centre
+        let side = pos + heading * ahead - *centre;
+        let clearance = obs_radius + radius;
+        if side.length_squared() >= clearance * clearance {
+            continue;
+        }
+
+        // dead centre, pick a side
+        let away = if side == Vec2::ZERO {
+            heading.perp()
+        } else {
+            side.normalize()
+        };
+        push += away * (1.0 - ahead / reach);
+    }
+    push
+}
+
+// direction to head in to approach `target` from `angle` radians off the straight line. the angle
+// shrinks as we close in, so flankers still end up on the target.
+pub fn flank(pos: Vec2, target: Vec2, angle: f32, commit_dist: f32) -> Vec2 {
+    let to = target - pos;
+    let t = (to.length() / commit_dist).min(1.0);
+    Mat2::from_angle(angle * t) * to.normalize_or_zero()
+}
@@ -0,0 +1,249 @@
+// reads arenas authored in Tiled (https://www.mapeditor.org), saved as json with their tilesets
+// embedded in the map. tile layers are drawn in order as the floor of the arena, and objects are
+// picked out by their class (type, in older versions of Tiled):
+//
+//   counter, table   rectangles that block things, like in level files
+//   pot              ellipses that block things, using their width as the diameter
+//   stove            rectangles that flare up. optional float properties `period` and `delay` for
+//                    the seconds between flares and before the first one
+//   conveyor         rectangles that push things, by float properties `dx` and `dy` every tick
+//   player_start     point the player starts at
+//   spawn            points enemies spawn around
+//
+// objects with any other class are ignored.
+use crate::hazards::{Hazard, STOVE_DEFAULT_PERIOD};
+use crate::json::{self, Value};
+use crate::level::{self, Kind, Level, Obstacle, Shape, Tiles, Tileset};
+use macroquad::prelude::*;
+
+// tiled keeps flip flags in the top bits of each tile id
+const GID_MASK: u32 = 0x0fff_ffff;
+
+pub fn parse(text: &str) -> Result<Level, String> {
+    let map = json::parse(text)?;
+
+    if map.get("infinite").and_then(Value::as_bool) == Some(true) {
+        return Err("infinite maps aren't supported".to_string());
+    }
+    if map.get("orientation").and_then(Value::as_str) != Some("orthogonal") {
+        return Err("only orthogonal maps are supported".to_string());
+    }
+
+    let width = num(&map, "width")?;
+    let height = num(&map, "height")?;
+    let tile_size = vec2(num(&map, "tilewidth")?, num(&map, "tileheight")?);
+
+    let mut tilesets = Vec::new();
+    for ts in arr(&map, "tilesets")? {
+        if ts.get("source").is_some() {
+            return Err("tilesets need to be embedded in the map".to_string());
+        }
+        tilesets.push(Tileset {
+            first_gid: num(ts, "firstgid")? as u32,
+            image: ts
+                .get("image")
+                .and_then(Value::as_str)
+                .ok_or("tileset has no image")?
+                .to_string(),
+            texture: None,
+            columns: num(ts, "columns")? as u32,
+            tile_size: vec2(num(ts, "tilewidth")?, num(ts, "tileheight")?),
+            margin: num(ts, "margin").unwrap_or(0.0),
+            spacing: num(ts, "spacing").unwrap_or(0.0),
+        });
+    }
+    // later tilesets have higher ids, lookups rely on this
+    tilesets.sort_by_key(|ts| ts.first_gid);
+
+    let mut level = Level {
+        size: vec2(width * tile_size.x, height * tile_size.y),
+        obstacles: Vec::new(),
+        hazards: Vec::new(),
+        tiles: None,
+        player_start: None,
+        spawn_points: Vec::new(),
+    };
+    if !level::valid_size(level.size) {
+        return Err(format!(
+            "the map's size needs to be more than 0 each way, not {}x{}",
+            level.size.x, level.size.y
+        ));
+    }
+    let mut layers = Vec::new();
+
+    for layer in arr(&map, "layers")? {
+        if layer.get("visible").and_then(Value::as_bool) == Some(false) {
+            continue;
+        }
+
+        match layer.get("type").and_then(Value::as_str) {
+            Some("tilelayer") => {
+                let data = arr(layer, "data").map_err(|_| "tile layers need csv encoding")?;
+                let gids: Option<Vec<u32>> = data
+                    .iter()
+                    .map(|v| v.as_u32().map(|g| g & GID_MASK))
+                    .collect();
+                layers.push(gids.ok_or("bad tile id")?);
+            }
+            Some("objectgroup") => {
+                for obj in arr(layer, "objects")? {
+                    read_object(obj, &mut level)?;
+                }
+            }
+            // image layers and groups aren't supported, but don't stop the map loading
+            _ => {}
+        }
+    }
+
+    if !layers.is_empty() {
+        level.tiles = Some(Tiles {
+            tile_size,
+            columns: width as usize,
+            layers,
+            tilesets,
+        });
+    }
+
+    Ok(level)
+}
+
+fn read_object(obj: &Value, level: &mut Level) -> Result<(), String> {
+    let class = obj
+        .get("class")
+        .or_else(|| obj.get("type"))
+        .and_then(Value::as_str)
+        .unwrap_or("");
+    let pos = vec2(num(obj, "x")?, num(obj, "y")?);
+    let size = vec2(num(obj, "width")?, num(obj, "height")?);
+    let rect = Rect::new(pos.x, pos.y, size.x, size.y);
+
+    match class {
+        "counter" => level.obstacles.push(Obstacle {
+            kind: Kind::Counter,
+            shape: Shape::Rect(rect),
+        }),
+        "table" => level.obstacles.push(Obstacle {
+            kind: Kind::Table,
+            shape: Shape::Rect(rect),
+        }),
+        "pot" => level.obstacles.push(Obstacle {
+            kind: Kind::Pot,
+            shape: Shape::Circle(pos + size / 2.0, size.x / 2.0),
+        }),
+        "stove" => level.hazards.push(Hazard::stove(
+            rect,
+            property(obj, "period").unwrap_or(STOVE_DEFAULT_PERIOD),
+            property(obj, "delay").unwrap_or(0.0),
+        )),
+        "conveyor" => level.hazards.push(Hazard::Conveyor {
+            rect,
+            push: vec2(
+                property(obj, "dx").unwrap_or(0.0),
+                property(obj, "dy").unwrap_or(0.0),
+            ),
+        }),
+        "player_start" => level.player_start = Some(pos),
+        "spawn" => level.spawn_points.push(pos),
+        _ => {}
+    }
+    Ok(())
+}
+
+// a custom property set on an object in Tiled
+fn property(obj: &Value, name: &str) -> Option<f32> {
+    obj.get("properties")?
+        .as_arr()?
+        .iter()
+        .find(|p| p.get("name").and_then(Value::as_str) == Some(name))?
+        .get("value")?
+        .as_f32()
+}
+
+fn num(v: &Value, key: &str) -> Result<f32, String> {
+    v.get(key)
+        .and_then(Value::as_f32)
+        .ok_or_else(|| format!("missing number '{}'", key))
+}
+
+fn arr<'a>(v: &'a Value, key: &str) -> Result<&'a [Value], String> {
+    v.get(key)
+        .and_then(Value::as_arr)
+        .ok_or_else(|| format!("missing list '{}'", key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a 2x1 map of 32 pixel tiles, with `data` as its tile layer and `objects` in an object layer
+    fn map(data: &str, objects: &str) -> String {
+        format!(
+            r#"{{
+                "orientation": "orthogonal", "infinite": false,
+                "width": 2, "height": 1, "tilewidth": 32, "tileheight": 32,
+                "tilesets": [{{
+                    "firstgid": 1, "image": "floor.png", "columns": 4,
+                    "tilewidth": 32, "tileheight": 32
+                }}],
+                "layers": [
+                    {{ "type": "tilelayer", "data": [{}] }},
+                    {{ "type": "objectgroup", "objects": [{}] }}
+                ]
+            }}"#,
+            data, objects
+        )
+    }
+
+    fn err(text: &str) -> String {
+        match parse(text) {
+            Err(e) => e,
+            Ok(_) => panic!("should be an error: {}", text),
+        }
+    }
+
+    #[test]
+    fn parses_the_default_kitchen() {
+        assert!(parse(include_str!("levels/kitchen.json")).is_ok());
+    }
+
+    #[test]
+    fn parses_tiles_and_objects() {
+        let level = parse(&map(
+            "1, 0",
+            r#"{ "type": "pot", "x": 10, "y": 20, "width": 30, "height": 30 },
+               { "class": "stove", "x": 0, "y": 0, "width": 32, "height": 32,
+                 "properties": [{ "name": "period", "type": "float", "value": 2 }] },
+               { "class": "player_start", "x": 5, "y": 6, "width": 0, "height": 0 },
+               { "class": "sign", "x": 0, "y": 0, "width": 1, "height": 1 }"#,
+        ))
+        .unwrap();
+        assert_eq!(level.size, vec2(64.0, 32.0));
+        assert_eq!(level.tiles.unwrap().layers, vec![vec![1, 0]]);
+        assert!(matches!(
+            level.obstacles[0].shape,
+            Shape::Circle(centre, 15.0) if centre == vec2(25.0, 35.0)
+        ));
+        assert_eq!(level.hazards.len(), 1);
+        assert_eq!(level.player_start, Some(vec2(5.0, 6.0)));
+    }
+
+    #[test]
+    fn keeps_flipped_tiles() {
+        // tile 5, flipped horizontally
+        let level = parse(&map("2147483653, 3221225474", "")).unwrap();
+        assert_eq!(level.tiles.unwrap().layers, vec![vec![5, 2]]);
+    }
+
+    #[test]
+    fn rejects_bad_maps() {
+        assert!(err(&map("1.5, 0", "")).contains("bad tile id"));
+        assert!(err(&map("-1, 0", "")).contains("bad tile id"));
+        assert!(
+            err(&map("1, 0", "").replace(r#""width": 2"#, r#""width": 0"#))
+                .contains("needs to be more than 0")
+        );
+        assert!(err(&map("1, 0", "").replace("false", "true")).contains("infinite"));
+        assert!(err(&map("1, 0", "").replace("orthogonal", "isometric")).contains("orthogonal"));
+        assert!(err(&map("1, 0", r#"{ "type": "pot" }"#)).contains("missing number 'x'"));
+    }
+}
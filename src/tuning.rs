@@ -0,0 +1,233 @@
+// gameplay numbers, for balancing the game without rebuilding it. `--tuning path` reads changes to
+// them from a file, with one `name value` per line, named as below. anything left out keeps the
+// value here. times are in ticks, 60 to a second, and speeds in pixels per tick.
+//
+// sizes aren't in here. they need to match the sprites, and the collision broadphase is built
+// around them.
+use crate::steering::Steering;
+use crate::TICKS_PER_SEC;
+use std::collections::HashMap;
+
+macro_rules! tuning {
+    ($($name:ident: $ty:ty = $default:expr,)*) => {
+        #[derive(Copy, Clone)]
+        pub struct Tuning {
+            $(pub $name: $ty,)*
+        }
+
+        impl Default for Tuning {
+            fn default() -> Self {
+                Self {
+                    $($name: $default,)*
+                }
+            }
+        }
+
+        impl Tuning {
+            fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+                match name {
+                    $(stringify!($name) => {
+                        self.$name = value.parse().map_err(|_| {
+                            format!("{} should be a {}, not '{}'", name, stringify!($ty), value)
+                        })?
+                    })*
+                    _ => return Err(format!("there's nothing called '{}' to tune", name)),
+                }
+                Ok(())
+            }
+        }
+    };
+}
+
+tuning! {
+    player_walk_speed: f32 = 2.0,
+    player_roll_speed: f32 = 6.0,
+    player_roll_ticks: i32 = 30,
+    // how long after a roll before the player can roll again
+    player_roll_recovery_ticks: i32 = 20,
+    // how far the knife is from the player
+    knife_reach: f32 = 35.0,
+
+    ticks_between_waves_min: i32 = TICKS_PER_SEC * 5,
+    ticks_between_waves_max: i32 = TICKS_PER_SEC * 3,
+    // how long enemies burn for after touching a flaring stove, before they're cooked
+    hazard_burn_ticks: i32 = TICKS_PER_SEC / 2,
+    // enemies don't spawn closer than this to the player
+    spawn_too_close: f32 = 250.0,
+
+    lemon_speed_wander: f32 = 0.5,
+    // how close a wandering lime gets to where it's going before picking somewhere else
+    lemon_wander_close: f32 = 10.0,
+    lemon_speed_attack: f32 = 1.8,
+    lemon_attacks_after_min: i32 = TICKS_PER_SEC * 3,
+    lemon_attacks_after_max: i32 = TICKS_PER_SEC * 20,
+    // how long before attacking a lime starts turning in to a lemon
+    lemon_ripen_ticks: i32 = TICKS_PER_SEC,
+    // attacking lemons pick an angle within this many radians to come at the player from, and
+    // commit to a straight line once within lemon_flank_commit of them
+    lemon_flank_angle: f32 = 0.9,
+    lemon_flank_commit: f32 = 150.0,
+    // steering, see steering.rs. 0 turns a behaviour off
+    lemon_separation: f32 = 1.5,
+    lemon_avoidance: f32 = 1.0,
+    lemon_flank: bool = true,
+
+    grape_attacks_after_min: i32 = 5 * TICKS_PER_SEC,
+    grape_attacks_after_max: i32 = 15 * TICKS_PER_SEC,
+    // grapes don't shoot when the player's this close, so they can't be hit point blank
+    grape_no_shoot_within: f32 = 100.0,
+    bullet_speed: f32 = 5.0,
+
+    bread_idle_min: i32 = 4 * TICKS_PER_SEC,
+    bread_idle_max: i32 = 6 * TICKS_PER_SEC,
+    // how long a bread shakes and shows where it's going before charging
+    bread_charge_warn_ticks: i32 = 3 * TICKS_PER_SEC,
+    bread_speed: f32 = 8.0,
+    // idle breads drift apart at this speed, so ones that charged at the same spot don't stay
+    // stacked
+    bread_drift_speed: f32 = 0.5,
+    // how hard idle breads push away from each other while drifting. 0 turns it off
+    bread_separation: f32 = 1.0,
+}
+
+impl Tuning {
+    pub fn lemon_steering(&self) -> Steering {
+        Steering {
+            separation: self.lemon_separation,
+            avoidance: self.lemon_avoidance,
+            flank: self.lemon_flank,
+        }
+    }
+
+    // breads only drift apart. they charge in straight lines, through anything in the way
+    pub fn bread_steering(&self) -> Steering {
+        Steering {
+            separation: self.bread_separation,
+            avoidance: 0.0,
+            flank: false,
+        }
+    }
+}
+
+pub async fn load(path: &str) -> Result<Tuning, String> {
+    let text = macroquad::file::load_string(path)
+        .await
+        .map_err(|e| e.to_string())?;
+    parse(&text).map_err(|e| format!("{}: {}", path, e))
+}
+
+pub fn parse(text: &str) -> Result<Tuning, String> {
+    let mut tuning = Tuning::default();
+    // the line each value was set on, for errors about values that don't fit together
+    let mut set_on = HashMap::new();
+    for (line_num, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("");
+        let mut words = line.split_whitespace();
+        let (name, value) = match (words.next(), words.next(), words.next()) {
+            (None, _, _) => continue,
+            (Some(name), Some(value), None) => (name, value),
+            _ => {
+                return Err(format!(
+                    "line {}: expected a name and a value, not '{}'",
+                    line_num + 1,
+                    line.trim()
+                ))
+            }
+        };
+        tuning
+            .set(name, value)
+            .map_err(|e| format!("line {}: {}", line_num + 1, e))?;
+        set_on.insert(name, line_num + 1);
+    }
+    check(&tuning, &set_on)?;
+    Ok(tuning)
+}
+
+// values that are fine on their own, but not with the others. errors point at the last line
+// that set one of the values involved
+fn check(t: &Tuning, set_on: &HashMap<&str, usize>) -> Result<(), String> {
+    let err = |names: &[&str], e: String| match names.iter().filter_map(|n| set_on.get(n)).max() {
+        Some(line) => format!("line {}: {}", line, e),
+        None => e,
+    };
+    for (name, ticks) in [
+        ("player_roll_ticks", t.player_roll_ticks),
+        ("player_roll_recovery_ticks", t.player_roll_recovery_ticks),
+    ] {
+        if ticks <= 0 {
+            return Err(err(
+                &[name],
+                format!("{} should be more than 0, not {}", name, ticks),
+            ));
+        }
+    }
+    if t.bread_idle_min > t.bread_idle_max {
+        return Err(err(
+            &["bread_idle_min", "bread_idle_max"],
+            format!(
+                "bread_idle_min ({}) can't be more than bread_idle_max ({})",
+                t.bread_idle_min, t.bread_idle_max
+            ),
+        ));
+    }
+    // breads start warning this long before charging, so they'd never get to sit idle
+    if t.bread_charge_warn_ticks >= t.bread_idle_min {
+        return Err(err(
+            &["bread_charge_warn_ticks", "bread_idle_min"],
+            format!(
+                "bread_charge_warn_ticks ({}) should be less than bread_idle_min ({})",
+                t.bread_charge_warn_ticks, t.bread_idle_min
+            ),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn err(text: &str) -> String {
+        match parse(text) {
+            Err(e) => e,
+            Ok(_) => panic!("'{}' should be an error", text),
+        }
+    }
+
+    #[test]
+    fn parses_tuning() {
+        let t = parse("# comment\n\nbullet_speed 7.5 # faster\nlemon_flank false\n").unwrap();
+        assert_eq!(t.bullet_speed, 7.5);
+        assert!(!t.lemon_flank);
+        assert_eq!(t.player_roll_ticks, Tuning::default().player_roll_ticks);
+    }
+
+    #[test]
+    fn rejects_bad_lines() {
+        assert!(err("\nbullet_speed").starts_with("line 2: expected a name and a value"));
+        assert!(err("bullet_speed 1 2").starts_with("line 1: expected a name and a value"));
+        assert_eq!(
+            err("bullet_speed fast"),
+            "line 1: bullet_speed should be a f32, not 'fast'"
+        );
+        assert_eq!(
+            err("knife_size 3"),
+            "line 1: there's nothing called 'knife_size' to tune"
+        );
+    }
+
+    #[test]
+    fn rejects_values_that_dont_fit_together() {
+        assert_eq!(
+            err("player_roll_ticks 0"),
+            "line 1: player_roll_ticks should be more than 0, not 0"
+        );
+        assert!(err("player_roll_recovery_ticks -5").starts_with("line 1: "));
+        assert!(err("bread_idle_max 400\nbread_idle_min 500").starts_with("line 2: bread_idle_min"));
+        assert!(err("bread_idle_min 100\nbread_idle_max 200")
+            .starts_with("line 1: bread_charge_warn_ticks (180) should be less than"));
+        assert!(
+            parse("bread_charge_warn_ticks 60\nbread_idle_min 100\nbread_idle_max 200").is_ok()
+        );
+    }
+}
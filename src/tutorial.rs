@@ -0,0 +1,255 @@
+// the tutorial. instead of the usual waves, it runs through a script of small waves, each with
+// something to do before moving on to the next. getting hit, or dicing what the step needed before
+// it got to attack, just starts the current step again.
+use crate::waves::Wave;
+use crate::TICKS_PER_SEC;
+use crate::{spawn_wave, BreadAim, GameState, PlayerState, BULLET_RADIUS, PLAYER_RADIUS};
+use macroquad::prelude::*;
+
+// how far the player has to walk to pass the first step
+const MOVE_DISTANCE: f32 = 200.0;
+// grapes normally wait a good while before their first shot. don't keep anyone waiting here
+const GRAPE_FIRST_SHOT_TICKS: i32 = 2 * TICKS_PER_SEC;
+// how long to show how they did before moving on, or starting the step again
+const FEEDBACK_TICKS: i32 = 2 * TICKS_PER_SEC;
+
+enum Goal {
+    // walk a little way from where the step started
+    Move,
+    // kill everything in the step's wave
+    ClearWave,
+    // roll through a bullet, rather than getting hit by it
+    RollThroughBullet,
+    // still be standing after a bread's charged
+    DodgeCharge,
+    // nothing left to do. enter starts a proper game
+    Finish,
+}
+
+struct Step {
+    text: &'static [&'static str],
+    wave: Wave,
+    goal: Goal,
+}
+
+static STEPS: [Step; 5] = [
+    Step {
+        text: &["WASD or the arrow keys to move. Have a wander"],
+        wave: Wave::EMPTY,
+        goal: Goal::Move,
+    },
+    Step {
+        text: &[
+            "Your knife dices up anything it touches",
+            "Go and get that lime before it ripens in to an angry lemon",
+        ],
+        wave: Wave::lems(1, 1),
+        goal: Goal::ClearWave,
+    },
+    Step {
+        text: &[
+            "You're allergic to all of this food. Don't let any of it touch you",
+            "Unless you're rolling, of course. Press space to roll through a grape's shot",
+        ],
+        wave: Wave {
+            grapes: (1, 1),
+            ..Wave::EMPTY
+        },
+        goal: Goal::RollThroughBullet,
+    },
+    Step {
+        text: &[
+            "Bread shakes and draws a line before it charges",
+            "Get out of the way, or roll through it",
+        ],
+        wave: Wave {
+            breads: (1, 1),
+            bread_aim: BreadAim::LockOnWarn,
+            ..Wave::EMPTY
+        },
+        goal: Goal::DodgeCharge,
+    },
+    Step {
+        text: &["That's everything. Press enter to play for real"],
+        wave: Wave::EMPTY,
+        goal: Goal::Finish,
+    },
+];
+
+#[derive(Copy, Clone)]
+pub struct Tutorial {
+    step: usize,
+    spawned: bool,
+    step_start_pos: Vec2,
+    // whether a bread's started charging this step
+    seen_charge: bool,
+    // when the current step was passed, or the player last got hit, or diced the step's enemies
+    // before they'd done what the step needed
+    passed_at: Option<i32>,
+    hit_at: Option<i32>,
+    diced_at: Option<i32>,
+}
+
+impl Tutorial {
+    pub fn new(player_pos: Vec2) -> Self {
+        Self {
+            step: 0,
+            spawned: false,
+            step_start_pos: player_pos,
+            seen_charge: false,
+            passed_at: None,
+            hit_at: None,
+            diced_at: None,
+        }
+    }
+
+    // 1 based, for showing to the player
+    pub fn step_num(&self) -> usize {
+        self.step + 1
+    }
+
+    pub fn step_count(&self) -> usize {
+        STEPS.len()
+    }
+
+    pub fn text(&self) -> &'static [&'static str] {
+        STEPS[self.step].text
+    }
+
+    // a word of encouragement for a little while after passing or failing a step
+    pub fn feedback(&self, tick: i32) -> Option<&'static str> {
+        let recent = |at: Option<i32>| at.is_some_and(|at| tick - at < FEEDBACK_TICKS);
+        if recent(self.passed_at) {
+            Some("Nice!")
+        } else if recent(self.diced_at) {
+            Some("Don't dice this one yet. Let it have a go at you first")
+        } else if recent(self.hit_at) {
+            Some("Ouch. Have another go")
+        } else {
+            None
+        }
+    }
+}
+
+// runs instead of the wave spawner
+pub fn tick(state: &mut GameState) {
+    let Some(mut t) = state.tutorial else {
+        return;
+    };
+
+    if !t.spawned {
+        start_step(state, &mut t);
+    }
+
+    match t.passed_at {
+        Some(at) if state.tick - at >= FEEDBACK_TICKS => {
+            clear_enemies(state);
+            t.step += 1;
+            t.spawned = false;
+            t.passed_at = None;
+        }
+        Some(_) => {}
+        None => {
+            if passed(state, &mut t) {
+                t.passed_at = Some(state.tick);
+            } else if out_of_enemies(state, &t) {
+                // the goal can't be met any more
+                restart_step(state, &mut t);
+                t.diced_at = Some(state.tick);
+                t.hit_at = None;
+            }
+        }
+    }
+
+    // the last step's finished by starting a proper game
+    if matches!(STEPS[t.step].goal, Goal::Finish) && is_key_down(KeyCode::Enter) {
+        *state = state.restart();
+        return;
+    }
+
+    state.tutorial = Some(t);
+}
+
+// called when the player gets hit. clears everything away and starts the step again, unless it's
+// already been passed
+pub fn retry(state: &mut GameState) {
+    let Some(mut t) = state.tutorial else {
+        return;
+    };
+    if t.passed_at.is_some() {
+        return;
+    }
+    restart_step(state, &mut t);
+    t.hit_at = Some(state.tick);
+    t.diced_at = None;
+    state.tutorial = Some(t);
+}
+
+// clears everything away, for the step to start again next tick
+fn restart_step(state: &mut GameState, t: &mut Tutorial) {
+    clear_enemies(state);
+    t.spawned = false;
+}
+
+fn start_step(state: &mut GameState, t: &mut Tutorial) {
+    spawn_wave(state, &STEPS[t.step].wave);
+    for g in &mut state.grapes {
+        g.attacks_in = g.attacks_in.min(GRAPE_FIRST_SHOT_TICKS);
+    }
+
+    t.spawned = true;
+    t.step_start_pos = state.player_pos;
+    t.seen_charge = false;
+}
+
+fn passed(state: &GameState, t: &mut Tutorial) -> bool {
+    match STEPS[t.step].goal {
+        Goal::Move => state.player_pos.distance(t.step_start_pos) >= MOVE_DISTANCE,
+        Goal::ClearWave => {
+            state.lemons.is_empty() && state.grapes.is_empty() && state.breads.is_empty()
+        }
+        Goal::RollThroughBullet => {
+            // checks every bullet rather than using the broadphase, which is out of date by now.
+            // bullets that went out of bounds this tick have already been removed
+            let touch_dist_sq = PLAYER_RADIUS * PLAYER_RADIUS + BULLET_RADIUS * BULLET_RADIUS;
+            let touching = state
+                .bullets
+                .iter()
+                .any(|b| b.pos.distance_squared(state.player_pos) < touch_dist_sq);
+            state.player_state() == PlayerState::Roll && touching
+        }
+        Goal::DodgeCharge => {
+            let charging = state.breads.iter().any(|b| b.is_charging());
+            if charging {
+                t.seen_charge = true;
+            }
+            // it's only over once the charge is
+            t.seen_charge && !charging
+        }
+        Goal::Finish => false,
+    }
+}
+
+// whether the step's enemies, and their bullets, are all gone without the goal being met. only
+// steps that need an enemy to attack can run out
+fn out_of_enemies(state: &GameState, t: &Tutorial) -> bool {
+    let all_gone = state.lemons.is_empty()
+        && state.grapes.is_empty()
+        && state.breads.is_empty()
+        && state.bullets.is_empty();
+    match STEPS[t.step].goal {
+        Goal::RollThroughBullet | Goal::DodgeCharge => all_gone,
+        Goal::Move | Goal::ClearWave | Goal::Finish => false,
+    }
+}
+
+// just takes them away. they aren't killed, so there's no score, corpses or effects
+fn clear_enemies(state: &mut GameState) {
+    state.lemons.clear();
+    state.grapes.clear();
+    state.breads.clear();
+    state.bullets.clear();
+
+    // this can happen after the grids were built for the tick, so they need redoing
+    state.broadphase.rebuild(&[], &[], &[], &[]);
+}
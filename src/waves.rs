@@ -1,97 +1,189 @@
-// enemy wave logic.
+// enemy wave logic. the waves themselves are in waves.txt, which is built in to the game.
+use crate::patterns;
+use crate::BreadAim;
+
 #[derive(Copy, Clone)]
 pub struct Wave {
     // pair of min/max number to spawn
     pub lemons: (u8, u8),
     pub grapes: (u8, u8),
     pub breads: (u8, u8),
+    // name of the bullet pattern grapes in this wave fire. see patterns.rs
+    pub grape_pattern: &'static str,
+    pub bread_aim: BreadAim,
 }
 
 impl Wave {
-    const fn lems(min: u8, max: u8) -> Self {
+    // nothing at all. handy to fill in the rest of a wave with `..Wave::EMPTY`
+    pub const EMPTY: Wave = Wave {
+        lemons: (0, 0),
+        grapes: (0, 0),
+        breads: (0, 0),
+        grape_pattern: "aimed",
+        bread_aim: BreadAim::Player,
+    };
+
+    pub const fn lems(min: u8, max: u8) -> Self {
         Wave {
             lemons: (min, max),
-            grapes: (0, 0),
-            breads: (0, 0),
+            ..Wave::EMPTY
         }
     }
 }
 
 impl Default for Wave {
     fn default() -> Self {
-        Self {
-            lemons: (0, 0),
-            grapes: (0, 0),
-            breads: (0, 0),
+        Self::EMPTY
+    }
+}
+
+#[derive(Clone)]
+pub struct Waves {
+    // played in order at the start of a game
+    set: Vec<Wave>,
+    // picked at random after that
+    late: Vec<Wave>,
+}
+
+pub const BUILT_IN: &str = include_str!("waves.txt");
+
+impl Waves {
+    pub fn built_in() -> Self {
+        parse(BUILT_IN).unwrap_or_else(|e| panic!("built in waves.txt: {}", e))
+    }
+
+    pub fn next_wave(&self, wave_num: i32) -> Wave {
+        let wave_num = wave_num as usize;
+
+        if wave_num < self.set.len() {
+            return self.set[wave_num];
         }
+
+        // not totally convinced macroquad's giving up inclusive or exclusive bounds, here
+        let wave_num = macroquad::rand::gen_range(0, self.late.len());
+        let wave_num = wave_num.clamp(0, self.late.len() - 1);
+        self.late[wave_num]
     }
 }
 
-static SET_WAVES: [Wave; 10] = [
-    Wave::lems(1, 1),
-    Wave::lems(3, 3),
-    Wave::lems(4, 6),
-    Wave::lems(4, 6),
-    Wave {
-        lemons: (4, 4),
-        grapes: (1, 2),
-        breads: (1, 1),
-    },
-    Wave {
-        lemons: (1, 2),
-        grapes: (3, 3),
-        breads: (1, 1),
-    },
-    Wave::lems(1, 1),
-    Wave::lems(1, 1),
-    Wave::lems(4, 6),
-    Wave {
-        lemons: (0, 0),
-        grapes: (5, 5),
-        breads: (3, 3),
-    },
-];
-
-static LATE_GAME_WAVES: [Wave; 5] = [
-    Wave {
-        lemons: (10, 20),
-        grapes: (0, 1),
-        breads: (0, 1),
-    },
-    Wave {
-        lemons: (0, 3),
-        grapes: (5, 10),
-        breads: (0, 1),
-    },
-    Wave {
-        lemons: (0, 0),
-        grapes: (0, 2),
-        breads: (5, 10),
-    },
-    Wave {
-        lemons: (2, 5),
-        grapes: (0, 4),
-        breads: (0, 2),
-    },
-    Wave {
-        lemons: (2, 5),
-        grapes: (0, 1),
-        breads: (0, 1),
-    },
-];
-
-// set list of waves to introduce the player to the game
-
-// list of end game waves to spawn
-pub fn next_wave(wave_num: i32) -> Wave {
-    let wave_num = wave_num as usize;
-
-    if wave_num < SET_WAVES.len() {
-        return SET_WAVES[wave_num];
+// see waves.txt for the format
+pub fn parse(text: &str) -> Result<Waves, String> {
+    let mut waves = Waves {
+        set: Vec::new(),
+        late: Vec::new(),
+    };
+
+    for (line_num, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("");
+        let err = |e: &str| format!("line {}: {}", line_num + 1, e);
+        let mut words = line.split_whitespace();
+        let list = match words.next() {
+            None => continue,
+            Some("set") => &mut waves.set,
+            Some("late") => &mut waves.late,
+            Some(other) => return Err(err(&format!("expected set or late, not '{}'", other))),
+        };
+
+        let mut wave = Wave::EMPTY;
+        while let Some(key) = words.next() {
+            let mut value = || {
+                words
+                    .next()
+                    .ok_or_else(|| err(&format!("{} needs a value", key)))
+            };
+            match key {
+                "lemons" | "grapes" | "breads" => {
+                    let mut count = || -> Result<u8, String> {
+                        value()?
+                            .parse()
+                            .map_err(|e| err(&format!("{}: {}", key, e)))
+                    };
+                    let range = (count()?, count()?);
+                    match key {
+                        "lemons" => wave.lemons = range,
+                        "grapes" => wave.grapes = range,
+                        _ => wave.breads = range,
+                    }
+                }
+                "pattern" => {
+                    let name = value()?;
+                    wave.grape_pattern = patterns::name(name)
+                        .ok_or_else(|| err(&format!("there's no pattern called '{}'", name)))?;
+                }
+                "aim" => {
+                    let name = value()?;
+                    wave.bread_aim = bread_aim(name)
+                        .ok_or_else(|| err(&format!("'{}' isn't a way to aim", name)))?;
+                }
+                _ => return Err(err(&format!("can't understand '{}'", key))),
+            }
+        }
+        list.push(wave);
+    }
+
+    if waves.late.is_empty() {
+        return Err("there needs to be at least one late wave".to_string());
+    }
+    Ok(waves)
+}
+
+// how breads aim, by the name waves.txt uses for it
+pub fn bread_aim(name: &str) -> Option<BreadAim> {
+    match name {
+        "player" => Some(BreadAim::Player),
+        "lock_on_warn" => Some(BreadAim::LockOnWarn),
+        "lead" => Some(BreadAim::Lead),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_built_in_waves() {
+        assert!(parse(BUILT_IN).is_ok());
+    }
+
+    #[test]
+    fn parses_waves() {
+        let waves = parse(
+            "# comment\n\
+             set lemons 1 2\n\
+             \n\
+             set grapes 0 3 pattern ring aim lead # comment\n\
+             late breads 2 4\n",
+        )
+        .unwrap();
+        assert_eq!(waves.set.len(), 2);
+        assert_eq!(waves.late.len(), 1);
+
+        let first = waves.set[0];
+        assert_eq!(first.lemons, (1, 2));
+        assert_eq!(first.grapes, (0, 0));
+        assert_eq!(first.grape_pattern, "aimed");
+        assert!(matches!(first.bread_aim, BreadAim::Player));
+
+        let second = waves.set[1];
+        assert_eq!(second.grapes, (0, 3));
+        assert_eq!(second.grape_pattern, "ring");
+        assert!(matches!(second.bread_aim, BreadAim::Lead));
+        assert_eq!(waves.late[0].breads, (2, 4));
     }
 
-    // not totally convinced macroquad's giving up inclusive or exclusive bounds, here
-    let wave_num = macroquad::rand::gen_range(0, LATE_GAME_WAVES.len());
-    let wave_num = wave_num.clamp(0, LATE_GAME_WAVES.len() - 1);
-    LATE_GAME_WAVES[wave_num]
+    #[test]
+    fn rejects_bad_waves() {
+        let err = |text| parse(text).err().unwrap();
+        assert_eq!(
+            err("set lemons 1 2"),
+            "there needs to be at least one late wave"
+        );
+        assert!(err("late\nearly lemons 1 2").starts_with("line 2: "));
+        assert!(err("late lemons 1").contains("lemons needs a value"));
+        assert!(err("late lemons 1 300").starts_with("line 1: lemons: "));
+        assert!(err("late pattern zigzag").contains("no pattern called 'zigzag'"));
+        assert!(err("late aim behind").contains("'behind' isn't a way to aim"));
+        assert!(err("late limes 1 2").contains("can't understand 'limes'"));
+    }
 }